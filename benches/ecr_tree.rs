@@ -0,0 +1,94 @@
+use bevy::{
+    ecs::{Schedule, SystemStage},
+    prelude::*,
+    reflect::TypeRegistry,
+};
+use bevy_debug_menu::widgets::{ecr_tree, overhead::Overhead, tree_node};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const ENTITIES: usize = 1000;
+
+/// World with a tree displaying the `Transform` of many entities, all expanded.
+/// With `skip_unchanged` false, all the components are visited on every update.
+fn setup(skip_unchanged: bool) -> (World, Resources, Schedule) {
+    let mut world = World::default();
+    let mut resources = Resources::default();
+    let type_registry = TypeRegistry::default();
+    {
+        let mut registry = type_registry.write();
+        registry.register::<Transform>();
+        registry.register::<Vec3>();
+        registry.register::<Quat>();
+    }
+    resources.insert(type_registry);
+    resources.insert(Overhead::default());
+
+    let entities = (0..ENTITIES)
+        .map(|_| world.spawn((Transform::default(),)))
+        .collect::<Vec<_>>();
+    let container = world.spawn((Children::default(),));
+    let mut state = ecr_tree::State::new(container, ecr_tree::Style::default());
+    state.set_skip_unchanged(skip_unchanged);
+    state
+        .get_root_keys_mut()
+        .extend(entities.iter().map(|entity| ecr_tree::Key::Component {
+            entity: *entity,
+            type_id: std::any::TypeId::of::<Transform>(),
+        }));
+    world.insert_one(container, state).unwrap();
+
+    let mut schedule = Schedule::default().with_stage(
+        "update",
+        SystemStage::serial()
+            .with_system(ecr_tree::ecr::update_system.system())
+            .with_system(ecr_tree::node::update_node_system.system()),
+    );
+    schedule.initialize_and_run(&mut world, &mut resources);
+
+    // Expand the components, then let the tree spawn their fields
+    let mut commands = Commands::default();
+    commands.set_entity_reserver(world.get_entity_reserver());
+    for (widget, mut node) in world.query_mut::<(Entity, &mut tree_node::Widget)>() {
+        if !node.is_expanded() {
+            node.toggle_expand(widget, &mut commands);
+        }
+    }
+    commands.apply(&mut world, &mut resources);
+    for _ in 0..2 {
+        schedule.initialize_and_run(&mut world, &mut resources);
+        world.clear_trackers();
+    }
+    (world, resources, schedule)
+}
+
+fn update(c: &mut Criterion) {
+    let (mut world, mut resources, mut schedule) = setup(true);
+    c.bench_function("update unchanged components", |b| {
+        b.iter(|| {
+            schedule.initialize_and_run(&mut world, &mut resources);
+            world.clear_trackers();
+        })
+    });
+
+    let (mut world, mut resources, mut schedule) = setup(false);
+    c.bench_function("update unchanged components, full visit", |b| {
+        b.iter(|| {
+            schedule.initialize_and_run(&mut world, &mut resources);
+            world.clear_trackers();
+        })
+    });
+
+    let (mut world, mut resources, mut schedule) = setup(true);
+    c.bench_function("update mutated components", |b| {
+        b.iter(|| {
+            for mut transform in world.query_filtered_mut::<&mut Transform, Without<Node>>() {
+                transform.translation.x += 1.;
+            }
+            schedule.initialize_and_run(&mut world, &mut resources);
+            world.clear_trackers();
+        })
+    });
+}
+
+criterion_group!(benches, update);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+use bevy::{prelude::*, ui};
+
+use crate::widgets::ecr_tree;
+use crate::{DebugIgnore, Overhead, OverheadPart};
+
+#[derive(Debug)]
+pub struct ArchetypeList;
+
+pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
+    let mut entity = None;
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: Rect {
+                        left: Val::Undefined,
+                        top: Val::Px(0.0), // We use this for vertical scrolling
+                        bottom: Val::Undefined,
+                        right: Val::Undefined,
+                    },
+                    size: Size {
+                        width: Val::Percent(100.),
+                        height: Val::Undefined, // Height will grow as needed
+                    },
+                    flex_shrink: 0.,
+                    padding: Rect {
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(Children::default())
+            .with(ArchetypeList)
+            .with(DebugIgnore);
+        entity = parent.current_entity();
+        parent.with(ecr_tree::State::new(entity.unwrap(), style.clone()));
+    });
+    entity.unwrap()
+}
+
+pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    // Empty archetypes and the ones of the debug menu are not shown
+    let archetypes = world
+        .archetypes
+        .iter()
+        .enumerate()
+        .filter(|(_, archetype)| archetype.len() > 0 && !archetype.has::<DebugIgnore>())
+        .map(|(index, _)| ecr_tree::Key::Archetype {
+            index: index as u32,
+        })
+        .collect::<Vec<_>>();
+
+    for mut state in world.query_filtered_mut::<&mut ecr_tree::State, With<ArchetypeList>>() {
+        let keys = state.get_root_keys_mut();
+        keys.clear();
+        keys.append(&mut archetypes.clone());
+    }
+}
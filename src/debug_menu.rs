@@ -7,13 +7,29 @@ use bevy::{
     ui,
 };
 
-use crate::{diagnostic, entity, resource, scene, widgets::*};
+use crate::{
+    archetype, component, diagnostic, entity, gizmo, highlight, history, overhead, picking,
+    resource, scene, selection, snapshot,
+    theme::{self, Theme},
+    ui_inspector, watch,
+    widgets::*,
+    Overhead, OverheadPart,
+};
 
 pub struct DebugMenuPlugin;
 
 impl Plugin for DebugMenuPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<Style>()
+        // The style is derived from the theme of the settings
+        app.init_resource::<DebugMenuSettings>()
+            .init_resource::<Style>()
+            .add_asset::<Theme>()
+            .init_asset_loader::<theme::ThemeLoader>()
+            .init_resource::<theme::ThemeFile>()
+            .add_startup_system(theme::load_system.system())
+            .add_system(theme::reload_system.system())
+            .add_system(theme::apply_system.system())
+            .add_system(read_only_system.system())
             // .register_type::<wgpu::AdapterInfo>()
             .add_startup_system(spawn_system.system())
             .add_system(update_system.system())
@@ -21,7 +37,43 @@ impl Plugin for DebugMenuPlugin {
             .add_system(selection_changed_event_system.system())
             .add_system(diagnostic::update_system.system())
             .add_system(entity::update_system.system()) // after check_box::update_mutated_system
-            .add_system(scene::interact_save_button.system());
+            .add_system(scene::interact_save_button.system())
+            .init_resource::<history::History>()
+            .add_system(history::record_system.system())
+            .add_system(history::handle_inputs_system.system())
+            .add_system(history::apply_system.system())
+            .add_system(history::update_system.system())
+            .init_resource::<watch::WatchList>()
+            .add_system(watch::pin_system.system())
+            .add_system(watch::pin_label_system.system()) // after watch::pin_system
+            .add_system(watch::update_system.system())
+            .add_system(archetype::update_system.system())
+            .add_system(component::update_system.system())
+            .init_resource::<selection::EntitySelection>()
+            .add_system(selection::select_system.system())
+            .add_system(selection::update_system.system())
+            .init_resource::<snapshot::Snapshots>()
+            .add_system(snapshot::request_system.system())
+            .add_system(snapshot::update_system.system())
+            .add_system(snapshot::update_list_system.system())
+            .init_resource::<picking::Picking>()
+            .add_event::<picking::PickedEvent>()
+            .add_system(toggle_mode_system::<picking::Picking>.system())
+            .add_system(picking::pick_system.system())
+            .add_system(picked_event_system.system())
+            .add_system(reveal_system.system())
+            .add_system(live_updates_system.system())
+            .init_resource::<ui_inspector::UiInspector>()
+            .add_system(toggle_mode_system::<ui_inspector::UiInspector>.system())
+            .add_system(ui_inspector::hover_system.system())
+            .add_system(ui_inspector::update_label_system.system())
+            .add_system(highlight::update_system.system())
+            .init_resource::<gizmo::Gizmo>()
+            .add_system(gizmo::update_target_system.system())
+            .add_system(gizmo::update_handles_system.system())
+            .add_system(gizmo::drag_system.system())
+            .add_startup_system(overhead::setup_system.system())
+            .add_system_to_stage(stage::LAST, overhead::diagnostic_system.system());
         #[cfg(feature = "extra")]
         app.init_resource::<resource::TestResource>()
             .register_type::<resource::TestResource>()
@@ -30,6 +82,40 @@ impl Plugin for DebugMenuPlugin {
     }
 }
 
+/// Options of the debug menu, insert this resource before adding the plugin to change them
+#[derive(Debug, Clone)]
+pub struct DebugMenuSettings {
+    /// Show the values without allowing any modification
+    pub read_only: bool,
+    /// Toggles the picking mode, where clicking an entity in the game shows it in the Entities panel
+    pub picking_key: KeyCode,
+    /// Toggles the UI inspection mode, where hovering a node of the game UI shows its layout
+    pub ui_inspector_key: KeyCode,
+    /// Number of times per second the displayed values are refreshed, every frame with None.
+    /// Lowering it reduces the cost of the menu in the frame time diagnostics.
+    pub refresh_rate: Option<f32>,
+    /// Colors, fonts and sizes of the menu, changing it restyles the menu
+    pub theme: Theme,
+    /// Theme file in the assets folder, replacing `theme` once loaded and whenever it is modified
+    pub theme_path: Option<String>,
+}
+
+impl Default for DebugMenuSettings {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            picking_key: KeyCode::F9,
+            ui_inspector_key: KeyCode::F11,
+            refresh_rate: None,
+            theme: Theme::default(),
+            theme_path: None,
+        }
+    }
+}
+
+/// Width of the scrollbar on the right of the panels, in pixels
+const SCROLLBAR_WIDTH: f32 = 8.0;
+
 // Marker component to filter out all of the debug menu's entities from the entity list
 #[derive(Debug, Clone, Copy)]
 pub struct DebugIgnore;
@@ -37,10 +123,23 @@ pub struct DebugIgnore;
 #[derive(Debug)]
 struct DebugMenu {
     menu_container: Entity,
+    /// Area below the header where the selected panel is spawned and scrolled
+    panel_container: Entity,
+    scrollbar: Entity,
+    scrollbar_thumb: Entity,
+    /// Check box pausing the refresh of the displayed values
+    live_updates_toggle: Entity,
     selected_panel: Panel,
     scrolling_position: f32,
+    /// Sizes measured on the last layout, used to scroll by pages and with the scrollbar
+    visible_height: f32,
+    panel_height: f32,
+    /// Cursor height and scrolling position when the scrollbar thumb was grabbed
+    scrollbar_drag: Option<(f32, f32)>,
     show_progress: f32,
     show: bool,
+    /// Picked entity that the Entities panel must expand and scroll to
+    reveal: Option<Entity>,
 }
 
 #[derive(Debug)]
@@ -50,6 +149,12 @@ enum Panel {
     Entity(Entity),
     Resource(Entity),
     Scene(Entity),
+    History(Entity),
+    Watch(Entity),
+    Archetype(Entity),
+    Component(Entity),
+    Selection(Entity),
+    Snapshot(Entity),
 }
 impl Panel {
     fn get_entity(&self) -> Entity {
@@ -59,6 +164,12 @@ impl Panel {
             Panel::Entity(e) => *e,
             Panel::Resource(e) => *e,
             Panel::Scene(e) => *e,
+            Panel::History(e) => *e,
+            Panel::Watch(e) => *e,
+            Panel::Archetype(e) => *e,
+            Panel::Component(e) => *e,
+            Panel::Selection(e) => *e,
+            Panel::Snapshot(e) => *e,
         }
     }
 }
@@ -72,12 +183,42 @@ pub struct Style {
     pub style_diagnostic: diagnostic::Style,
     pub style_list: ecr_tree::Style,
     pub style_scene: scene::Style,
+    pub style_history: history::Style,
+    pub color_scrollbar: Handle<ColorMaterial>,
+    pub color_scrollbar_thumb: Handle<ColorMaterial>,
+    /// Drawn over the entities of the game that are hovered or selected in the menu
+    pub color_entity_highlight: Handle<ColorMaterial>,
+    /// Handles to move, rotate and scale the last selected entity
+    pub style_gizmo: gizmo::Style,
     #[cfg(feature = "extra")]
     pub z_index: ui::ZIndex,
+    /// Theme this style is derived from
+    theme: Theme,
+    assets: StyleAssets,
+}
+
+/// Fonts, icons and materials shared by the styles derived from successive themes
+#[derive(Debug, Clone)]
+struct StyleAssets {
+    font: Handle<Font>,
+    font_mono: Handle<Font>,
+    icon_chevron_down: Handle<ColorMaterial>,
+    icon_chevron_up: Handle<ColorMaterial>,
+    icon_toggle_on: Handle<ColorMaterial>,
+    icon_toggle_off: Handle<ColorMaterial>,
+    icon_toggle_off_hovered: Handle<ColorMaterial>,
+    icon_toggle_mixed: Handle<ColorMaterial>,
+    icon_remove: Handle<ColorMaterial>,
+    color_root_container: Handle<ColorMaterial>,
+    materials: theme::Materials,
 }
 
 impl FromResources for Style {
     fn from_resources(resources: &Resources) -> Self {
+        let theme = resources
+            .get::<DebugMenuSettings>()
+            .map(|settings| settings.theme.clone())
+            .unwrap_or_default();
         let mut materials = resources.get_mut::<Assets<ColorMaterial>>().unwrap();
         let mut assets_font = resources.get_mut::<Assets<Font>>().unwrap();
         let font = {
@@ -102,11 +243,17 @@ impl FromResources for Style {
                 assets_texture.add(load_texture(ICON_CHEVRON_UP)),
             ))
         };
-        let icon_toggle_on = {
+        let (icon_toggle_on, icon_toggle_mixed) = {
             const ICON_TOGGLE_ON: &[u8] = include_bytes!("../assets/toggle-on.png");
-            materials.add(ColorMaterial::texture(
-                assets_texture.add(load_texture(ICON_TOGGLE_ON)),
-            ))
+            let texture = assets_texture.add(load_texture(ICON_TOGGLE_ON));
+            (
+                materials.add(ColorMaterial::texture(texture.clone())),
+                // Faded to show that the values differ
+                materials.add(ColorMaterial::modulated_texture(
+                    texture,
+                    Color::rgba(1.0, 1.0, 1.0, 0.4),
+                )),
+            )
         };
         let icon_toggle_off = {
             const ICON_TOGGLE_OFF: &[u8] = include_bytes!("../assets/toggle-off.png");
@@ -121,20 +268,60 @@ impl FromResources for Style {
                 assets_texture.add(load_texture(ICON_TOGGLE_OFF_HOVERED)),
             ))
         };
+        let icon_remove = {
+            const ICON_REMOVE: &[u8] = include_bytes!("../assets/x.png");
+            materials.add(ColorMaterial::texture(
+                assets_texture.add(load_texture(ICON_REMOVE)),
+            ))
+        };
 
-        let style_tree_node = tree_node::Style {
+        let assets = StyleAssets {
+            font,
+            font_mono,
+            icon_chevron_down,
+            icon_chevron_up,
+            icon_toggle_on,
+            icon_toggle_off,
+            icon_toggle_off_hovered,
+            icon_toggle_mixed,
+            icon_remove,
             color_root_container: materials.add(Color::NONE.into()),
-            color_button: materials.add(Color::BLACK.into()),
-            color_button_hovered: Some(materials.add(Color::MIDNIGHT_BLUE.into())),
-            color_button_clicked: Some(materials.add(Color::ALICE_BLUE.into())),
-            color_button_expanded: Some(materials.add(Color::MIDNIGHT_BLUE.into())),
-            color_children_container: materials.add(Color::rgba(0.2, 0.2, 0.2, 0.8).into()),
+            materials: theme::Materials::new(&theme, &mut materials),
+        };
+        let asset_server = resources.get::<AssetServer>();
+        Style::new(theme, assets, asset_server.as_deref())
+    }
+}
+
+impl Style {
+    /// Derive all the styles of the menu from a theme
+    fn new(theme: Theme, assets: StyleAssets, asset_server: Option<&AssetServer>) -> Self {
+        let load_font = |path: &Option<String>, embedded: &Handle<Font>| match (path, asset_server)
+        {
+            (Some(path), Some(asset_server)) => asset_server.load(path.as_str()),
+            (Some(path), None) => {
+                warn!("Cannot load font {} without an AssetServer", path);
+                embedded.clone()
+            }
+            (None, _) => embedded.clone(),
+        };
+        let font = load_font(&theme.font, &assets.font);
+        let font_mono = load_font(&theme.font_mono, &assets.font_mono);
+        let materials = &assets.materials;
+
+        let style_tree_node = tree_node::Style {
+            color_root_container: assets.color_root_container.clone(),
+            color_button: materials.button.clone(),
+            color_button_hovered: Some(materials.button_hovered.clone()),
+            color_button_clicked: Some(materials.button_clicked.clone()),
+            color_button_expanded: Some(materials.button_hovered.clone()),
+            color_children_container: materials.children_container.clone(),
             node_style_button: ui::Style {
                 flex_shrink: 0.,
                 margin: Rect {
                     left: Val::Px(0.0),
                     right: Val::Px(0.0),
-                    top: Val::Px(4.0),
+                    top: Val::Px(theme.spacing),
                     bottom: Val::Px(0.0),
                 },
                 ..Default::default()
@@ -149,10 +336,10 @@ impl FromResources for Style {
                     bottom: Val::Px(0.0),
                 },
                 padding: Rect {
-                    left: Val::Px(8.0),
+                    left: Val::Px(theme.indent),
                     right: Val::Px(0.0),
                     top: Val::Px(0.0),
-                    bottom: Val::Px(4.0),
+                    bottom: Val::Px(theme.spacing),
                 },
                 ..Default::default()
             },
@@ -160,7 +347,7 @@ impl FromResources for Style {
 
         let style_menu = radio_button::Style {
             style_container: ui::Style {
-                padding: Rect::all(Val::Px(2.0)),
+                padding: Rect::all(Val::Px(theme.spacing / 2.)),
                 flex_shrink: 0.,
                 flex_wrap: FlexWrap::Wrap,
                 #[cfg(feature = "extra")]
@@ -171,7 +358,7 @@ impl FromResources for Style {
             style_button: ui::Style {
                 flex_direction: FlexDirection::ColumnReverse,
                 flex_grow: 1.0,
-                margin: Rect::all(Val::Px(2.0)),
+                margin: Rect::all(Val::Px(theme.spacing / 2.)),
                 ..Default::default()
             },
             color_button: style_tree_node.color_button.clone(),
@@ -180,7 +367,7 @@ impl FromResources for Style {
             color_button_selected: style_tree_node.color_button_hovered.clone(),
         };
 
-        let color_background = materials.add(Color::rgb(0.8, 0.8, 0.8).into());
+        let color_background = materials.background.clone();
 
         let style_diagnostic = diagnostic::Style {
             font: font_mono,
@@ -192,53 +379,112 @@ impl FromResources for Style {
                 margin: Rect {
                     left: Val::Px(0.0),
                     right: Val::Px(0.0),
-                    top: Val::Px(4.0),
+                    top: Val::Px(theme.spacing),
                     bottom: Val::Px(0.0),
                 },
                 ..Default::default()
             },
-            font_size: 18.,
+            font_size: theme.font_size,
+            spacing: theme.spacing,
+            color_text: theme.color_text,
         };
 
-        let style_list = ecr_tree::Style {
+        let mut style_list = ecr_tree::Style {
             font: font.clone(),
+            font_size: theme.font_size,
+            spacing: theme.spacing,
             color_background: color_background.clone(),
             style_node: style_tree_node,
-            color_node_text: Color::WHITE,
+            color_node_text: theme.color_text,
+            color_text: theme.color_title_text,
             style_input_box: input_box::Style {
-                color_background: materials.add(Color::WHITE.into()),
+                color_background: materials.input_box.clone(),
                 font: font.clone(),
+                font_size: 0.,
+                color_text: theme.color_input_text,
             },
-            icon_chevron_down,
-            icon_chevron_up,
+            icon_chevron_down: assets.icon_chevron_down.clone(),
+            icon_chevron_up: assets.icon_chevron_up.clone(),
             style_check_box: check_box::Style {
-                icon_toggle_on,
-                icon_toggle_off,
-                icon_toggle_off_hovered: Some(icon_toggle_off_hovered),
+                icon_toggle_on: assets.icon_toggle_on.clone(),
+                icon_toggle_off: assets.icon_toggle_off.clone(),
+                icon_toggle_off_hovered: Some(assets.icon_toggle_off_hovered.clone()),
                 icon_toggle_on_hovered: None,
+                icon_toggle_mixed: Some(assets.icon_toggle_mixed.clone()),
+                spacing: theme.spacing,
             },
+            icon_remove: assets.icon_remove.clone(),
+            color_highlight: theme.color_highlight,
         };
+        // The input boxes have the size of the small texts of the tree
+        style_list.style_input_box.font_size = style_list.font_size_small();
 
         let style_scene = scene::Style {
             font: font.clone(),
-            font_size: 18.0,
+            font_size: theme.font_size,
+            spacing: theme.spacing,
+            color_background: color_background.clone(),
+            color_button: style_diagnostic.color_box.clone(),
+            color_text: theme.color_text,
+        };
+
+        let style_history = history::Style {
+            font: font.clone(),
+            font_size: theme.font_size,
+            spacing: theme.spacing,
             color_background: color_background.clone(),
+            color_box: style_diagnostic.color_box.clone(),
+            color_button: style_diagnostic.color_box.clone(),
+            style_box: style_diagnostic.style_box.clone(),
+            color_text_applied: theme.color_text,
+            color_text_undone: theme.color_text_disabled,
+            color_text: theme.color_title_text,
         };
 
         Style {
             color_background,
             font,
-            color_title_text: Color::BLACK,
+            color_title_text: theme.color_title_text,
             style_menu,
             // style_entity_list,
             style_diagnostic,
             style_list,
             style_scene,
+            style_history,
+            color_scrollbar: materials.scrollbar.clone(),
+            color_scrollbar_thumb: materials.scrollbar_thumb.clone(),
+            color_entity_highlight: materials.entity_highlight.clone(),
+            style_gizmo: gizmo::Style {
+                size: 12.0,
+                color_translate: materials.gizmo_translate.clone(),
+                color_translate_x: materials.gizmo_translate_x.clone(),
+                color_translate_y: materials.gizmo_translate_y.clone(),
+                color_rotate: materials.gizmo_rotate.clone(),
+                color_scale: materials.gizmo_scale.clone(),
+            },
             // In front of default layers
             #[cfg(feature = "extra")]
             z_index: ZIndex::Some(10),
+            theme,
+            assets,
         }
     }
+
+    /// Theme this style is derived from
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Derive the style from another theme, the materials of this style are recolored
+    pub(crate) fn with_theme(
+        &self,
+        theme: &Theme,
+        materials: &mut Assets<ColorMaterial>,
+        asset_server: &AssetServer,
+    ) -> Style {
+        self.assets.materials.set_colors(theme, materials);
+        Style::new(theme.clone(), self.assets.clone(), Some(asset_server))
+    }
 }
 
 fn spawn_system(commands: &mut Commands, style: Res<Style>) {
@@ -299,11 +545,23 @@ fn spawn_system(commands: &mut Commands, style: Res<Style>) {
                     ),
                     ..Default::default()
                 })
+                .with(TextRole::Title)
                 .with(DebugIgnore);
         })
         .current_entity()
         .unwrap();
-    let menus = ["Diagnostics", "Entities", "Resources", "Scenes"];
+    let menus = [
+        "Diagnostics",
+        "Entities",
+        "Resources",
+        "Scenes",
+        "History",
+        "Watch",
+        "Archetypes",
+        "Components",
+        "Selection",
+        "Snapshots",
+    ];
     let mut radio_button = None;
     commands.with_children(|parent| {
         radio_button = Some(parent.spawn_radio_buttons(
@@ -343,30 +601,152 @@ fn spawn_system(commands: &mut Commands, style: Res<Style>) {
         });
     }
 
+    // Toggle to pause the refresh of the displayed values
+    let mut live_updates_row = None;
+    commands.set_current_entity(container);
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    align_self: AlignSelf::Center,
+                    flex_shrink: 0.,
+                    margin: Rect::all(Val::Px(style.style_list.spacing)),
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle {
+                        text: Text::with_section(
+                            "Live updates".to_string(),
+                            TextStyle {
+                                font: style.font.clone(),
+                                font_size: style.style_list.font_size,
+                                color: style.color_title_text,
+                            },
+                            Default::default(),
+                        ),
+                        style: ui::Style {
+                            align_self: AlignSelf::Center,
+                            size: Size {
+                                width: Val::Undefined,
+                                height: Val::Px(style.style_list.font_size),
+                            },
+                            flex_shrink: 0.,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(TextRole::Title)
+                    .with(DebugIgnore);
+            });
+        live_updates_row = parent.current_entity();
+    });
+    commands.set_current_entity(live_updates_row.unwrap());
+    let live_updates_toggle = commands
+        .spawn_check_box(
+            true,
+            style.style_list.style_check_box.clone(),
+            Some(ecr_tree::with_debug_ignore),
+        )
+        .widget;
+
     let mut default_panel = None;
+    let mut panel_container = None;
+    let mut scrollbar = None;
+    let mut scrollbar_thumb = None;
     commands.set_current_entity(container);
     commands.with_children(|parent| {
-        parent.spawn(NodeBundle::default());
-        default_panel = parent.current_entity();
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    // Take the height left below the header, the panel overflows it
+                    flex_direction: FlexDirection::ColumnReverse,
+                    flex_grow: 1.,
+                    flex_basis: Val::Px(0.),
+                    min_size: Size::new(Val::Undefined, Val::Px(0.)),
+                    // Room for the scrollbar
+                    padding: Rect {
+                        right: Val::Px(SCROLLBAR_WIDTH),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: ui::Style {
+                            display: Display::None,
+                            position_type: PositionType::Absolute,
+                            position: Rect {
+                                right: Val::Px(0.),
+                                top: Val::Px(0.),
+                                bottom: Val::Px(0.),
+                                ..Default::default()
+                            },
+                            size: Size::new(Val::Px(SCROLLBAR_WIDTH), Val::Undefined),
+                            flex_direction: FlexDirection::ColumnReverse,
+                            ..Default::default()
+                        },
+                        material: style.color_scrollbar.clone(),
+                        ..Default::default()
+                    })
+                    .with(DebugIgnore)
+                    .with_children(|parent| {
+                        parent
+                            .spawn(ButtonBundle {
+                                style: ui::Style {
+                                    size: Size::new(Val::Percent(100.), Val::Px(0.)),
+                                    flex_shrink: 0.,
+                                    ..Default::default()
+                                },
+                                material: style.color_scrollbar_thumb.clone(),
+                                ..Default::default()
+                            })
+                            .with(DebugIgnore);
+                        scrollbar_thumb = parent.current_entity();
+                    });
+                scrollbar = parent.current_entity();
+                parent.spawn(NodeBundle::default());
+                default_panel = parent.current_entity();
+            });
+        panel_container = parent.current_entity();
     });
 
     commands.set_current_entity(container);
     commands.with(DebugMenu {
         menu_container: radio_button.as_ref().unwrap().widget,
+        panel_container: panel_container.unwrap(),
+        scrollbar: scrollbar.unwrap(),
+        scrollbar_thumb: scrollbar_thumb.unwrap(),
+        live_updates_toggle,
         selected_panel: Panel::Default(default_panel.unwrap()),
         scrolling_position: Default::default(),
+        visible_height: Default::default(),
+        panel_height: Default::default(),
+        scrollbar_drag: None,
         show_progress: Default::default(),
         show: Default::default(),
+        reveal: None,
     });
     // println!("{}", r#"{"reason":"compiler-message","package_id":"test_bevy 0.1.0 (path+file:///home/davierb/prog/ltu_prototype)","target":{"kind":["bin"],"crate_types":["bin"],"name":"test_bevy","src_path":"/home/davierb/prog/ltu_prototype/src/main.rs","edition":"2018","doc":true,"doctest":false,"test":true},"message":{"rendered":"warning: unused import: `bevy::prelude::*`\n --> src/test.rs:1:5\n  |\n1 | use bevy::prelude::*;\n  |     ^^^^^^^^^^^^^^^^\n  |\n  = note: `#[warn(unused_imports)]` on by default\n\n","children":[{"children":[],"code":null,"level":"note","message":"`#[warn(unused_imports)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"remove the whole `use` item","rendered":null,"spans":[{"byte_end":21,"byte_start":0,"column_end":22,"column_start":1,"expansion":null,"file_name":"src/test.rs","is_primary":true,"label":null,"line_end":1,"line_start":1,"suggested_replacement":"","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":22,"highlight_start":1,"text":"use bevy::prelude::*;"}]}]}],"code":{"code":"unused_imports","explanation":null},"level":"warning","message":"unused import: `bevy::prelude::*`","spans":[{"byte_end":20,"byte_start":4,"column_end":21,"column_start":5,"expansion":null,"file_name":"src/test.rs","is_primary":true,"label":null,"line_end":1,"line_start":1,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":21,"highlight_start":5,"text":"use bevy::prelude::*;"}]}]}}"#);
 }
 
 fn update_system(
     time: Res<Time>,
-    windows: Res<Windows>,
     mut query_debug_menu: Query<(Entity, &mut DebugMenu), With<DebugIgnore>>,
     mut query_style: Query<(&mut ui::Style, &Node), With<DebugIgnore>>,
+    mut query_state: Query<&mut ecr_tree::State>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Menu);
     if let Some((entity, mut debug_menu)) = query_debug_menu.iter_mut().next() {
         // Horizontal transition
         if let Ok((mut debug_menu_style, _)) = query_style.get_mut(entity) {
@@ -393,13 +773,15 @@ fn update_system(
             }
         }
 
+        let visible_height = match query_style.get_mut(debug_menu.panel_container) {
+            Ok((_, container_node)) => container_node.size.y,
+            Err(_) => return,
+        };
         if let Ok((mut panel_style, panel_node)) =
             query_style.get_mut(debug_menu.selected_panel.get_entity())
         {
-            let window_height = windows.get_primary().unwrap().height();
-            let header_height = window_height * 0.15;
             let panel_height = panel_node.size.y;
-            let max_scroll = window_height - panel_height - header_height; // FIXME: use bottom pos of panel - botton pos of window
+            let max_scroll = (visible_height - panel_height).min(0.);
 
             // Vertical scrolling
             if debug_menu.scrolling_position < max_scroll {
@@ -412,20 +794,113 @@ fn update_system(
             if new_position_top != panel_style.position.top {
                 panel_style.position.top = new_position_top;
             }
+            debug_menu.visible_height = visible_height;
+            debug_menu.panel_height = panel_height;
+        }
+
+        // The Entities panel only spawns the nodes that are visible
+        if let Panel::Entity(panel) = debug_menu.selected_panel {
+            if let Ok(mut state) = query_state.get_mut(panel) {
+                state.set_viewport(Some(ecr_tree::viewport::Viewport {
+                    offset: -debug_menu.scrolling_position,
+                    height: visible_height,
+                }));
+            }
+        }
+
+        // The scrollbar is only shown when the panel overflows
+        let (display, thumb_height, thumb_top) =
+            if visible_height > 0. && debug_menu.panel_height > visible_height {
+                let ratio = visible_height / debug_menu.panel_height;
+                (
+                    Display::Flex,
+                    visible_height * ratio,
+                    -debug_menu.scrolling_position * ratio,
+                )
+            } else {
+                (Display::None, 0., 0.)
+            };
+        if let Ok((mut scrollbar_style, _)) = query_style.get_mut(debug_menu.scrollbar) {
+            if scrollbar_style.display != display {
+                scrollbar_style.display = display;
+            }
+        }
+        if let Ok((mut thumb_style, _)) = query_style.get_mut(debug_menu.scrollbar_thumb) {
+            if thumb_style.size.height != Val::Px(thumb_height) {
+                thumb_style.size.height = Val::Px(thumb_height);
+            }
+            if thumb_style.position.top != Val::Px(thumb_top) {
+                thumb_style.position.top = Val::Px(thumb_top);
+            }
         }
     }
 }
 
+#[allow(clippy::clippy::too_many_arguments)]
 fn handle_inputs_system(
     mut mousewheel_events: EventReader<MouseWheel>,
     keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<&mut DebugMenu>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut query: Query<(&mut DebugMenu, &Node, &GlobalTransform)>,
+    query_interaction: Query<&Interaction>,
+    query_cursor: Query<&input_box::Cursor>,
+    overhead: Res<Overhead>,
 ) {
-    if let Some(mut debug_menu) = query.iter_mut().next() {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if let Some((mut debug_menu, node, transform)) = query.iter_mut().next() {
+        let cursor = windows
+            .get_primary()
+            .and_then(|window| window.cursor_position());
+        // The mouse wheel is left to the game when the cursor is not over the menu
+        let hovered = debug_menu.show
+            && cursor.map_or(false, |cursor| {
+                picking::ui_contains(node, transform, cursor)
+            });
         for ev in mousewheel_events.iter() {
-            // TODO: check if mouse is over the menu
-            debug_menu.scrolling_position += ev.y * 40.0;
+            if hovered {
+                debug_menu.scrolling_position += ev.y * 40.0;
+            }
+        }
+
+        // Dragging the scrollbar thumb, the panel moves by as much as the thumb relatively to their containers
+        match (debug_menu.scrollbar_drag, cursor) {
+            (Some(_), _) if !mouse_input.pressed(MouseButton::Left) => {
+                debug_menu.scrollbar_drag = None;
+            }
+            (Some((start_cursor, start_position)), Some(cursor)) => {
+                if debug_menu.visible_height > 0. {
+                    debug_menu.scrolling_position = start_position
+                        + (cursor.y - start_cursor) * debug_menu.panel_height
+                            / debug_menu.visible_height;
+                }
+            }
+            (None, Some(cursor)) => {
+                if let Ok(Interaction::Clicked) = query_interaction.get(debug_menu.scrollbar_thumb)
+                {
+                    debug_menu.scrollbar_drag = Some((cursor.y, debug_menu.scrolling_position));
+                }
+            }
+            _ => {}
+        }
+
+        // Keyboard navigation, unless text is being edited
+        if debug_menu.show && !input_box::text_input_focused(&query_cursor) {
+            if keyboard_input.just_pressed(KeyCode::PageUp) {
+                debug_menu.scrolling_position += debug_menu.visible_height;
+            }
+            if keyboard_input.just_pressed(KeyCode::PageDown) {
+                debug_menu.scrolling_position -= debug_menu.visible_height;
+            }
+            // The scrolling position is clamped by update_system()
+            if keyboard_input.just_pressed(KeyCode::Home) {
+                debug_menu.scrolling_position = 0.;
+            }
+            if keyboard_input.just_pressed(KeyCode::End) {
+                debug_menu.scrolling_position = -debug_menu.panel_height;
+            }
         }
+
         if keyboard_input.just_pressed(KeyCode::F10) {
             debug_menu.show = !debug_menu.show;
             debug_menu.show_progress = 1.0;
@@ -436,15 +911,18 @@ fn handle_inputs_system(
 fn selection_changed_event_system(
     commands: &mut Commands,
     mut radio_button_events: EventReader<radio_button::SelectionChangedEvent>,
-    mut query: Query<(Entity, &mut DebugMenu)>,
+    mut query: Query<&mut DebugMenu>,
     style: Res<Style>,
+    settings: Res<DebugMenuSettings>,
+    overhead: Res<Overhead>,
 ) {
-    if let Some((debug_menu_entity, mut debug_menu)) = query.iter_mut().next() {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if let Some(mut debug_menu) = query.iter_mut().next() {
         for event in radio_button_events.iter() {
             if event.widget == debug_menu.menu_container {
                 let previous_panel = debug_menu.selected_panel.get_entity();
                 commands.despawn_recursive(previous_panel);
-                commands.set_current_entity(debug_menu_entity);
+                commands.set_current_entity(debug_menu.panel_container);
                 match event.new_selection {
                     None => {
                         let mut default_panel = None;
@@ -471,6 +949,32 @@ fn selection_changed_event_system(
                         let scene_container = scene::spawn(commands, &style.style_scene);
                         debug_menu.selected_panel = Panel::Scene(scene_container);
                     }
+                    Some(4) => {
+                        let history_container =
+                            history::spawn(commands, &style.style_history, settings.read_only);
+                        debug_menu.selected_panel = Panel::History(history_container);
+                    }
+                    Some(5) => {
+                        let watch_container = watch::spawn(commands, &style.style_list);
+                        debug_menu.selected_panel = Panel::Watch(watch_container);
+                    }
+                    Some(6) => {
+                        let archetype_container = archetype::spawn(commands, &style.style_list);
+                        debug_menu.selected_panel = Panel::Archetype(archetype_container);
+                    }
+                    Some(7) => {
+                        let component_container = component::spawn(commands, &style.style_list);
+                        debug_menu.selected_panel = Panel::Component(component_container);
+                    }
+                    Some(8) => {
+                        let selection_container = selection::spawn(commands, &style.style_list);
+                        debug_menu.selected_panel = Panel::Selection(selection_container);
+                    }
+                    Some(9) => {
+                        let snapshot_container =
+                            snapshot::spawn(commands, &style.style_history, settings.read_only);
+                        debug_menu.selected_panel = Panel::Snapshot(snapshot_container);
+                    }
                     _ => unreachable!(),
                 }
             }
@@ -478,6 +982,164 @@ fn selection_changed_event_system(
     }
 }
 
+/// Open the Entities panel to show the picked entity
+fn picked_event_system(
+    mut picked_events: EventReader<picking::PickedEvent>,
+    mut query_debug_menu: Query<&mut DebugMenu>,
+    mut query_menu: Query<&mut radio_button::Widget>,
+    mut query_material: Query<&mut Handle<ColorMaterial>>,
+    mut selection_changed_events: ResMut<Events<radio_button::SelectionChangedEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if let Some(mut debug_menu) = query_debug_menu.iter_mut().next() {
+        for event in picked_events.iter() {
+            if !debug_menu.show {
+                debug_menu.show = true;
+                debug_menu.show_progress = 1.0;
+            }
+            debug_menu.reveal = Some(event.entity);
+            if let Ok(mut menu) = query_menu.get_mut(debug_menu.menu_container) {
+                // The Entities panel is the second menu
+                if let Some(changed) =
+                    menu.select_index(debug_menu.menu_container, 1, &mut query_material)
+                {
+                    selection_changed_events.send(changed);
+                }
+            }
+        }
+    }
+}
+
+/// Expand the node of the picked entity and scroll to it, once the Entities panel displays it
+fn reveal_system(
+    commands: &mut Commands,
+    mut query_debug_menu: Query<&mut DebugMenu>,
+    mut query_node: Query<(
+        Entity,
+        &mut tree_node::Widget,
+        &ecr_tree::EntryAccess,
+        &Node,
+        &GlobalTransform,
+    )>,
+    query_state: Query<&ecr_tree::State>,
+    mut expanded_events: ResMut<Events<tree_node::ExpandedEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if let Some(mut debug_menu) = query_debug_menu.iter_mut().next() {
+        let (entity, panel) = match (debug_menu.reveal, &debug_menu.selected_panel) {
+            (Some(entity), Panel::Entity(panel)) => (entity, *panel),
+            _ => return,
+        };
+        let key = ecr_tree::Key::Entity { entity };
+        for (widget, mut node, access, ui_node, transform) in query_node.iter_mut() {
+            if access.state_entity() != panel || *access.key() != key {
+                continue;
+            }
+            // Wait for the layout of the node
+            if ui_node.size.y <= 0. {
+                return;
+            }
+            if !node.is_expanded() {
+                node.toggle_expand(widget, commands);
+                expanded_events.send(tree_node::ExpandedEvent {
+                    widget,
+                    expanded: true,
+                });
+            }
+            // Move the top of the node just below the header, at the top of the panel container
+            let node_top = transform.translation.y + ui_node.size.y / 2.;
+            debug_menu.scrolling_position += node_top - debug_menu.visible_height;
+            debug_menu.reveal = None;
+            return;
+        }
+        // The node is not spawned while it is outside of the viewport, scroll to where it will be
+        if let Some(offset) = query_state
+            .get(panel)
+            .ok()
+            .and_then(|state| state.root_key_offset(&key))
+        {
+            debug_menu.scrolling_position = -offset;
+        }
+    }
+}
+
+/// Apply the refresh rate of the settings, and pause or resume the refresh with the check box of the header
+fn live_updates_system(
+    settings: Res<DebugMenuSettings>,
+    mut toggled_events: EventReader<check_box::ToggledEvent>,
+    query_debug_menu: Query<&DebugMenu>,
+    mut live_updates: ResMut<ecr_tree::LiveUpdates>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if live_updates.refresh_rate != settings.refresh_rate {
+        live_updates.refresh_rate = settings.refresh_rate;
+    }
+    if let Some(debug_menu) = query_debug_menu.iter().next() {
+        for event in toggled_events.iter() {
+            if event.entity == debug_menu.live_updates_toggle {
+                live_updates.paused = !event.checked;
+            }
+        }
+    }
+}
+
+/// Mode of the debug menu that is enabled and disabled with a key of the settings
+pub(crate) trait ToggledMode: Send + Sync + 'static {
+    /// Name of the mode in the logs
+    const NAME: &'static str;
+    fn key(settings: &DebugMenuSettings) -> KeyCode;
+    fn enabled_mut(&mut self) -> &mut bool;
+}
+
+pub(crate) fn toggle_mode_system<M: ToggledMode>(
+    settings: Res<DebugMenuSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    query_cursor: Query<&input_box::Cursor>,
+    mut mode: ResMut<M>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if input_box::text_input_focused(&query_cursor) {
+        return;
+    }
+    if keyboard_input.just_pressed(M::key(&settings)) {
+        let enabled = mode.enabled_mut();
+        *enabled = !*enabled;
+        info!(
+            "{} {}",
+            M::NAME,
+            if *enabled { "enabled" } else { "disabled" }
+        );
+    }
+}
+
+/// Apply the read-only mode to all the trees, including the inspectors spawned outside of the menu,
+/// and again to all of them when the settings change
+fn read_only_system(
+    settings: Res<DebugMenuSettings>,
+    mut applied: Local<Option<bool>>,
+    mut queries: QuerySet<(
+        Query<&mut ecr_tree::State, Added<ecr_tree::State>>,
+        Query<&mut ecr_tree::State>,
+    )>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if *applied != Some(settings.read_only) {
+        *applied = Some(settings.read_only);
+        for mut state in queries.q1_mut().iter_mut() {
+            state.set_read_only(settings.read_only);
+        }
+    } else if settings.read_only {
+        for mut state in queries.q0_mut().iter_mut() {
+            state.set_read_only(true);
+        }
+    }
+}
+
 // Extracted from ImageTextureLoader::load()
 fn load_texture(bytes: &[u8]) -> Texture {
     let dyn_img = image::load_from_memory(bytes).unwrap();
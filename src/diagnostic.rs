@@ -4,11 +4,14 @@ use bevy::{
     ui,
 };
 
-use crate::DebugIgnore;
+use crate::{
+    widgets::{ecr_tree::LiveUpdates, TextRole},
+    DebugIgnore, Overhead, OverheadPart,
+};
 
 #[derive(Debug)]
 pub struct DiagnosticList {
-    style: Style,
+    pub(crate) style: Style,
 }
 
 #[derive(Debug)]
@@ -20,9 +23,12 @@ pub struct DiagnosticListItem {
 pub struct Style {
     pub font: Handle<Font>,
     pub font_size: f32,
+    /// Margin around the texts, in pixels
+    pub spacing: f32,
     pub color_background: Handle<ColorMaterial>,
     pub color_box: Handle<ColorMaterial>,
     pub style_box: ui::Style,
+    pub color_text: Color,
 }
 
 pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
@@ -44,8 +50,8 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
                     },
                     flex_shrink: 0.,
                     padding: Rect {
-                        left: Val::Px(4.0),
-                        right: Val::Px(4.0),
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
                         top: Val::Px(0.0),
                         bottom: Val::Px(0.0),
                     },
@@ -67,9 +73,13 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
 pub fn update_system(
     commands: &mut Commands,
     query_list: Query<(Entity, &Children, &DiagnosticList)>,
-    mut query_item: Query<(&DiagnosticListItem, &mut Text)>,
+    mut query_item: Query<(Entity, &DiagnosticListItem, &mut Text)>,
+    query_new_item: Query<(), Added<DiagnosticListItem>>,
     diagnostics: Res<Diagnostics>,
+    live_updates: Res<LiveUpdates>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Diagnostics);
     for (entity, items, widget) in query_list.iter() {
         // If # of diagnostics changed, rebuild the list
         if diagnostics.iter().count() != query_item.iter_mut().count() {
@@ -99,7 +109,7 @@ pub fn update_system(
                                         TextStyle {
                                             font: widget.style.font.clone(),
                                             font_size: widget.style.font_size,
-                                            color: Color::WHITE,
+                                            color: widget.style.color_text,
                                         },
                                         TextAlignment {
                                             vertical: VerticalAlign::Center,
@@ -114,11 +124,12 @@ pub fn update_system(
                                         },
                                         flex_shrink: 0.,
                                         flex_grow: 1.,
-                                        margin: Rect::all(Val::Px(4.0)),
+                                        margin: Rect::all(Val::Px(widget.style.spacing)),
                                         ..Default::default()
                                     },
                                     ..Default::default()
                                 })
+                                .with(TextRole::Text)
                                 .with(DebugIgnore)
                                 .spawn(TextBundle {
                                     text: Text::with_section(
@@ -126,7 +137,7 @@ pub fn update_system(
                                         TextStyle {
                                             font: widget.style.font.clone(),
                                             font_size: widget.style.font_size,
-                                            color: Color::WHITE,
+                                            color: widget.style.color_text,
                                         },
                                         TextAlignment {
                                             vertical: VerticalAlign::Center,
@@ -140,11 +151,12 @@ pub fn update_system(
                                             height: Val::Px(widget.style.font_size),
                                         },
                                         flex_shrink: 0.,
-                                        margin: Rect::all(Val::Px(4.0)),
+                                        margin: Rect::all(Val::Px(widget.style.spacing)),
                                         ..Default::default()
                                     },
                                     ..Default::default()
                                 })
+                                .with(TextRole::Text)
                                 .with(DiagnosticListItem { id: diagnostic.id })
                                 .with(DebugIgnore);
                         });
@@ -152,8 +164,11 @@ pub fn update_system(
             }
         }
 
-        // For each text, update
-        for (item, mut text) in query_item.iter_mut() {
+        // For each text, update at the refresh rate of the live values, new items are filled immediately
+        for (entity, item, mut text) in query_item.iter_mut() {
+            if !live_updates.is_refreshing() && query_new_item.get(entity).is_err() {
+                continue;
+            }
             if let Some(diagnostic) = diagnostics.get(item.id) {
                 text.sections[0].value = format!("{:.3}", diagnostic.average().unwrap_or(f64::NAN));
             }
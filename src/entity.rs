@@ -1,10 +1,13 @@
-use bevy::{prelude::*, ui};
+use bevy::{prelude::*, reflect::TypeRegistry, ui, utils::HashSet};
 
 use crate::widgets::ecr_tree;
-use crate::DebugIgnore;
+use crate::{list, DebugIgnore, Overhead, OverheadPart};
 
-#[derive(Debug)]
-pub struct EntityList;
+#[derive(Debug, Default)]
+pub struct EntityList {
+    /// Length and last entity of each archetype when the entities were last collected
+    archetypes: Vec<(usize, Option<Entity>)>,
+}
 
 pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
     let mut entity = None;
@@ -26,8 +29,8 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
                     },
                     flex_shrink: 0.,
                     padding: Rect {
-                        left: Val::Px(4.0),
-                        right: Val::Px(4.0),
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
                         top: Val::Px(0.0),
                         bottom: Val::Px(0.0),
                     },
@@ -37,20 +40,85 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
                 ..Default::default()
             })
             .with(Children::default())
-            .with(EntityList)
-            .with(DebugIgnore);
+            .with(EntityList::default())
+            .with(DebugIgnore)
+            .with_children(|parent| list::spawn_unregistered_summary(parent, style));
         entity = parent.current_entity();
         parent.with(ecr_tree::State::new(entity.unwrap(), style.clone()));
     });
     entity.unwrap()
 }
 
-pub fn update_system(world: &mut World, _resources: &mut Resources) {
+/// Collect the entities again when the archetypes were modified, instead of on every frame
+fn archetypes_changed(world: &mut World) -> bool {
+    let archetypes = world
+        .archetypes
+        .iter()
+        .map(|archetype| {
+            let len = archetype.len() as usize;
+            // An entity spawned in place of a despawned one is the last of its archetype
+            let last = len
+                .checked_sub(1)
+                .and_then(|index| archetype.iter_entities().nth(index).copied());
+            (len, last)
+        })
+        .collect::<Vec<_>>();
+    let mut changed = false;
+    for mut list in world.query_mut::<&mut EntityList>() {
+        if list.archetypes != archetypes {
+            list.archetypes = archetypes.clone();
+            changed = true;
+        }
+    }
+    changed
+}
+
+pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    if !archetypes_changed(world) {
+        return;
+    }
     let debugged_entities = world
         .query_filtered::<Entity, Without<DebugIgnore>>()
         .map(|entity| ecr_tree::Key::Entity { entity })
         .collect::<Vec<_>>();
 
+    // Count the component types that cannot be displayed
+    let mut unregistered = HashSet::default();
+    let mut not_reflect_component = HashSet::default();
+    {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let type_registry = type_registry.read();
+        for archetype in world.archetypes.iter() {
+            if archetype.len() == 0 || archetype.has::<DebugIgnore>() {
+                continue;
+            }
+            for type_info in archetype.types() {
+                match type_registry.get(type_info.id()) {
+                    None => {
+                        unregistered.insert(type_info.id());
+                    }
+                    Some(registration) if registration.data::<ReflectComponent>().is_none() => {
+                        not_reflect_component.insert(type_info.id());
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    let summary = if unregistered.is_empty() && not_reflect_component.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "{} component types are not registered, {} are not a ReflectComponent",
+            unregistered.len(),
+            not_reflect_component.len()
+        )
+    };
+    list::set_unregistered_summary::<EntityList>(world, summary);
+
     for mut state in world.query_filtered_mut::<&mut ecr_tree::State, With<EntityList>>() {
         let keys = state.get_root_keys_mut();
         keys.clear();
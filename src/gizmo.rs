@@ -0,0 +1,400 @@
+use std::{any::TypeId, sync::Arc};
+
+use bevy::{
+    prelude::*,
+    reflect::{Struct, TypeRegistry},
+    render::{camera::Camera, render_graph::base::camera::CAMERA_2D},
+};
+
+use crate::{
+    history::History,
+    picking,
+    selection::EntitySelection,
+    widgets::ecr_tree::{self, attributes::FieldAttribute, PathSegment, ReflectPath},
+    DebugIgnore, Overhead, OverheadPart,
+};
+
+/// Handles drawn around the last selected entity to edit its `Transform` in the game world
+#[derive(Debug, Default)]
+pub struct Gizmo {
+    target: Option<Entity>,
+    handles: Vec<Entity>,
+    drag: Option<Drag>,
+}
+
+#[derive(Debug)]
+struct Drag {
+    handle: GizmoHandle,
+    /// Cursor position in the space of the parent of the target, where its `Transform` is expressed
+    start_cursor: Vec3,
+    start: Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoHandle {
+    Translate,
+    TranslateX,
+    TranslateY,
+    Rotate,
+    Scale,
+}
+
+impl GizmoHandle {
+    const ALL: [GizmoHandle; 5] = [
+        GizmoHandle::Translate,
+        GizmoHandle::TranslateX,
+        GizmoHandle::TranslateY,
+        GizmoHandle::Rotate,
+        GizmoHandle::Scale,
+    ];
+
+    /// Position relative to the target, in multiples of the handle size.
+    /// The translation handles follow the axes of the parent, in which the `Transform` is expressed.
+    fn offset(self, rotation: Quat, parent_rotation: Quat) -> Vec3 {
+        match self {
+            GizmoHandle::Translate => Vec3::zero(),
+            GizmoHandle::TranslateX => parent_rotation * Vec3::new(4., 0., 0.),
+            GizmoHandle::TranslateY => parent_rotation * Vec3::new(0., 4., 0.),
+            // The rotation and scale handles follow the rotation of the target
+            GizmoHandle::Rotate => rotation * Vec3::new(-4., 4., 0.),
+            GizmoHandle::Scale => rotation * Vec3::new(4., 4., 0.),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Style {
+    /// Size of the handles in pixels, for a camera that is not scaled
+    pub size: f32,
+    pub color_translate: Handle<ColorMaterial>,
+    pub color_translate_x: Handle<ColorMaterial>,
+    pub color_translate_y: Handle<ColorMaterial>,
+    pub color_rotate: Handle<ColorMaterial>,
+    pub color_scale: Handle<ColorMaterial>,
+}
+
+impl Style {
+    fn material(&self, handle: GizmoHandle) -> Handle<ColorMaterial> {
+        match handle {
+            GizmoHandle::Translate => self.color_translate.clone(),
+            GizmoHandle::TranslateX => self.color_translate_x.clone(),
+            GizmoHandle::TranslateY => self.color_translate_y.clone(),
+            GizmoHandle::Rotate => self.color_rotate.clone(),
+            GizmoHandle::Scale => self.color_scale.clone(),
+        }
+    }
+}
+
+/// Fields of `Transform` edited by the handles
+const TRANSFORM_FIELDS: [&str; 3] = ["translation", "rotation", "scale"];
+
+fn transform_key(entity: Entity, field: &str, type_id: TypeId) -> ecr_tree::Key {
+    ecr_tree::Key::ReflectLeaf {
+        path: ReflectPath {
+            root: Arc::new(ecr_tree::Key::Component {
+                entity,
+                type_id: TypeId::of::<Transform>(),
+            }),
+            segments: vec![PathSegment::Field(field.to_string())],
+        },
+        type_id,
+        variant_index: None,
+    }
+}
+
+/// Apply the field attributes of `Transform` to a dragged value, like the tree does to the values of its widgets:
+/// the read-only fields keep their value from the start of the drag and the ranges are applied
+fn apply_attributes(type_registry: &TypeRegistry, start: &Transform, transform: &mut Transform) {
+    for field in TRANSFORM_FIELDS.iter() {
+        let attribute = FieldAttribute::of_field(
+            type_registry,
+            TypeId::of::<Transform>(),
+            field,
+            &FieldAttribute::default(),
+        );
+        let value = transform.field_mut(field).unwrap();
+        if attribute.read_only {
+            value.apply(start.field(field).unwrap());
+        } else {
+            attribute.constrain(value);
+        }
+    }
+}
+
+/// Follow the last selected entity, spawning the handles for it
+pub fn update_target_system(
+    commands: &mut Commands,
+    settings: Res<crate::DebugMenuSettings>,
+    style: Res<crate::Style>,
+    selection: Res<EntitySelection>,
+    mut gizmo: ResMut<Gizmo>,
+    query_target: Query<(), (With<Transform>, Without<DebugIgnore>)>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    // Moving the handles is a modification too
+    let target = if settings.read_only {
+        None
+    } else {
+        selection
+            .entities
+            .last()
+            .copied()
+            .filter(|entity| query_target.get(*entity).is_ok())
+    };
+    if target == gizmo.target {
+        return;
+    }
+    trace!("Gizmo target changed: {:?}", target);
+    for handle in gizmo.handles.drain(..) {
+        commands.despawn(handle);
+    }
+    gizmo.drag = None;
+    gizmo.target = target;
+    if target.is_none() {
+        return;
+    }
+
+    for handle in GizmoHandle::ALL.iter() {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite::new(Vec2::new(style.style_gizmo.size, style.style_gizmo.size)),
+                material: style.style_gizmo.material(*handle),
+                ..Default::default()
+            })
+            .with(*handle)
+            .with(DebugIgnore);
+        gizmo.handles.push(commands.current_entity().unwrap());
+    }
+}
+
+/// Global transform of the parent of an entity, the space in which its `Transform` is expressed
+fn parent_transform(
+    entity: Entity,
+    query_parent: &Query<&Parent>,
+    query_global: &Query<&GlobalTransform>,
+) -> GlobalTransform {
+    query_parent
+        .get(entity)
+        .ok()
+        .and_then(|parent| query_global.get(parent.0).ok())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Place the handles around the target, with a constant size on screen
+pub fn update_handles_system(
+    gizmo: Res<Gizmo>,
+    style: Res<crate::Style>,
+    query_camera: Query<(&Camera, &GlobalTransform)>,
+    query_parent: Query<&Parent>,
+    query_global: Query<&GlobalTransform>,
+    mut query_handle: Query<(&GizmoHandle, &mut Transform)>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let (target, target_transform) = match gizmo
+        .target
+        .and_then(|target| Some((target, *query_global.get(target).ok()?)))
+    {
+        Some(target) => target,
+        None => return,
+    };
+    let parent_rotation = parent_transform(target, &query_parent, &query_global).rotation;
+    let camera_scale = query_camera
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA_2D))
+        .map_or(1., |(_, transform)| transform.scale.x);
+    let scale = style.style_gizmo.size * camera_scale;
+    for (handle, mut transform) in query_handle.iter_mut() {
+        let mut translation = target_transform.translation
+            + handle.offset(target_transform.rotation, parent_rotation) * scale;
+        // In front of the target and of its highlight
+        translation.z += 1.;
+        let new_transform = Transform {
+            translation,
+            rotation: target_transform.rotation,
+            scale: Vec3::new(camera_scale, camera_scale, 1.),
+        };
+        if *transform != new_transform {
+            *transform = new_transform;
+        }
+    }
+}
+
+/// Drag the handles to move, rotate or scale the target.
+/// The target is written while dragging like a value restored from the history: the field attributes apply
+/// and the trees show the new value without highlighting it as an external change.
+/// The whole drag is recorded in the history as a single change on release.
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn drag_system(
+    mut gizmo: ResMut<Gizmo>,
+    mut history: ResMut<History>,
+    type_registry: Res<TypeRegistry>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    query_camera: Query<(&Camera, &GlobalTransform)>,
+    query_ui: Query<(&Node, &GlobalTransform), With<DebugIgnore>>,
+    query_handle: Query<(&GizmoHandle, &GlobalTransform, &Sprite)>,
+    query_parent: Query<&Parent>,
+    query_global: Query<&GlobalTransform>,
+    mut query_target: Query<&mut Transform, Without<GizmoHandle>>,
+    mut query_state: Query<&mut ecr_tree::State>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let target = match gizmo.target {
+        Some(target) => target,
+        None => return,
+    };
+    let target_z = match query_global.get(target) {
+        Ok(target_transform) => target_transform.translation.z,
+        Err(_) => return,
+    };
+    let mut transform = match query_target.get_mut(target) {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    // The cursor is projected on the plane of the target
+    let cursor = window.cursor_position().and_then(|cursor| {
+        query_camera.iter().find_map(|(camera, camera_transform)| {
+            let (origin, direction) =
+                picking::cursor_ray(window, cursor, camera, camera_transform)?;
+            if direction.z.abs() < f32::EPSILON {
+                return None;
+            }
+            let t = (target_z - origin.z) / direction.z;
+            Some(origin + direction * t)
+        })
+    });
+    // The drag is computed in the space of the parent, like the `Transform` it modifies
+    let to_parent_space = parent_transform(target, &query_parent, &query_global)
+        .compute_matrix()
+        .inverse();
+    let local_cursor = cursor.map(|cursor| to_parent_space.transform_point3(cursor));
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let over_menu = window.cursor_position().map_or(false, |position| {
+            query_ui
+                .iter()
+                .any(|(node, ui_transform)| picking::ui_contains(node, ui_transform, position))
+        });
+        if let (Some(cursor), Some(local_cursor), false) = (cursor, local_cursor, over_menu) {
+            let grabbed = query_handle
+                .iter()
+                .find(|(_, handle_transform, sprite)| {
+                    let half_size = sprite.size * handle_transform.scale.truncate() / 2.;
+                    let distance = (cursor - handle_transform.translation).truncate();
+                    distance.x.abs() <= half_size.x && distance.y.abs() <= half_size.y
+                })
+                .map(|(handle, ..)| *handle);
+            if let Some(handle) = grabbed {
+                trace!("Dragging gizmo handle {:?}", handle);
+                gizmo.drag = Some(Drag {
+                    handle,
+                    start_cursor: local_cursor,
+                    start: *transform,
+                });
+            }
+        }
+    }
+
+    let drag = match &gizmo.drag {
+        Some(drag) => drag,
+        None => return,
+    };
+    if let Some(cursor) = local_cursor {
+        let start = drag.start;
+        let delta = cursor - drag.start_cursor;
+        let mut new_transform = start;
+        match drag.handle {
+            GizmoHandle::Translate => {
+                new_transform.translation += Vec3::new(delta.x, delta.y, 0.);
+            }
+            GizmoHandle::TranslateX => new_transform.translation.x += delta.x,
+            GizmoHandle::TranslateY => new_transform.translation.y += delta.y,
+            GizmoHandle::Rotate => {
+                let angle = |position: Vec3| {
+                    let offset = position - start.translation;
+                    offset.y.atan2(offset.x)
+                };
+                let rotation = Quat::from_rotation_z(angle(cursor) - angle(drag.start_cursor));
+                new_transform.rotation = rotation * start.rotation;
+            }
+            GizmoHandle::Scale => {
+                let distance = |position: Vec3| (position - start.translation).truncate().length();
+                let start_distance = distance(drag.start_cursor);
+                if start_distance > f32::EPSILON {
+                    let factor = distance(cursor) / start_distance;
+                    new_transform.scale = start.scale * Vec3::new(factor, factor, 1.);
+                }
+            }
+        }
+        apply_attributes(&type_registry, &start, &mut new_transform);
+        if *transform != new_transform {
+            for field in TRANSFORM_FIELDS.iter() {
+                let value = new_transform.field(field).unwrap();
+                if !transform
+                    .field(field)
+                    .unwrap()
+                    .reflect_partial_eq(value)
+                    .unwrap_or(false)
+                {
+                    let key = transform_key(target, field, value.type_id());
+                    for mut state in query_state.iter_mut() {
+                        state.refresh_leaf(&key, value);
+                    }
+                }
+            }
+            *transform = new_transform;
+        }
+    }
+
+    if mouse_input.just_released(MouseButton::Left) {
+        // The target keeps its final value, the history only records the change
+        let end = *transform;
+        let mut edits: Vec<(&str, TypeId, Box<dyn Reflect>, Box<dyn Reflect>)> = Vec::new();
+        if end.translation != drag.start.translation {
+            edits.push((
+                "translation",
+                TypeId::of::<Vec3>(),
+                Box::new(drag.start.translation),
+                Box::new(end.translation),
+            ));
+        }
+        if end.rotation != drag.start.rotation {
+            edits.push((
+                "rotation",
+                TypeId::of::<Quat>(),
+                Box::new(drag.start.rotation),
+                Box::new(end.rotation),
+            ));
+        }
+        if end.scale != drag.start.scale {
+            edits.push((
+                "scale",
+                TypeId::of::<Vec3>(),
+                Box::new(drag.start.scale),
+                Box::new(end.scale),
+            ));
+        }
+        for (field, type_id, old_value, new_value) in edits {
+            history.push(
+                None,
+                ecr_tree::Change {
+                    key: transform_key(target, field, type_id),
+                    label: format!("{:?} Transform.{}", target, field),
+                    type_name: new_value.type_name().to_string(),
+                    old_value,
+                    new_value,
+                    old_values: Vec::new(),
+                },
+            );
+        }
+        gizmo.drag = None;
+    }
+}
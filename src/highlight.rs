@@ -0,0 +1,189 @@
+use bevy::{prelude::*, ui};
+
+use crate::{
+    selection::EntitySelection,
+    ui_inspector::UiInspector,
+    widgets::{ecr_tree, tree_node},
+    DebugIgnore, Overhead, OverheadPart,
+};
+
+/// Overlay drawn over an entity of the game that is hovered or selected in the menu
+#[derive(Debug)]
+pub struct EntityHighlight {
+    target: Entity,
+}
+
+/// Area covered by an entity, in the game world or in the UI
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outline {
+    World { transform: Transform, size: Vec2 },
+    Ui { center: Vec2, size: Vec2 },
+}
+
+/// Size of the outline of entities that have neither a sprite, a mesh nor a UI node
+const DEFAULT_SIZE: f32 = 16.;
+
+fn outline(
+    query_target: &Query<(
+        &GlobalTransform,
+        Option<&Sprite>,
+        Option<&Handle<Mesh>>,
+        Option<&Node>,
+    )>,
+    meshes: &Assets<Mesh>,
+    target: Entity,
+) -> Option<Outline> {
+    let (global_transform, sprite, mesh, node) = query_target.get(target).ok()?;
+    if let Some(node) = node {
+        return Some(Outline::Ui {
+            center: global_transform.translation.truncate(),
+            size: node.size,
+        });
+    }
+    let mut transform = Transform {
+        translation: global_transform.translation,
+        rotation: global_transform.rotation,
+        scale: global_transform.scale,
+    };
+    // In front of the entity
+    transform.translation.z += 0.5;
+    let size = if let Some(sprite) = sprite {
+        sprite.size
+    } else if let Some((min, max)) = mesh
+        .and_then(|mesh| meshes.get(mesh))
+        .and_then(crate::picking::mesh_bounds)
+    {
+        let center = (min + max) / 2.;
+        transform.translation += transform.rotation * (transform.scale * center);
+        (max - min).truncate()
+    } else {
+        Vec2::new(DEFAULT_SIZE, DEFAULT_SIZE)
+    };
+    Some(Outline::World { transform, size })
+}
+
+fn ui_style_for(center: Vec2, size: Vec2) -> ui::Style {
+    ui::Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(center.x - size.x / 2.),
+            bottom: Val::Px(center.y - size.y / 2.),
+            ..Default::default()
+        },
+        size: Size::new(Val::Px(size.x), Val::Px(size.y)),
+        ..Default::default()
+    }
+}
+
+/// Draw an overlay over the entity of the hovered node, over the selected entities and over the inspected UI node
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn update_system(
+    commands: &mut Commands,
+    style: Res<crate::Style>,
+    selection: Res<EntitySelection>,
+    inspector: Res<UiInspector>,
+    meshes: Res<Assets<Mesh>>,
+    query_button: Query<(&Interaction, &Parent), With<tree_node::Button>>,
+    query_access: Query<&ecr_tree::EntryAccess>,
+    mut queries: QuerySet<(
+        Query<(
+            &GlobalTransform,
+            Option<&Sprite>,
+            Option<&Handle<Mesh>>,
+            Option<&Node>,
+        )>,
+        Query<(
+            Entity,
+            &EntityHighlight,
+            Option<&mut Transform>,
+            Option<&mut Sprite>,
+            Option<&mut ui::Style>,
+        )>,
+    )>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let hovered = query_button
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .filter_map(|(_, parent)| query_access.get(parent.0).ok())
+        .find_map(|access| match *access.key() {
+            ecr_tree::Key::Entity { entity } => Some(entity),
+            _ => None,
+        });
+    let mut targets = selection.entities.clone();
+    for hovered in hovered.into_iter().chain(inspector.hovered()) {
+        if !targets.contains(&hovered) {
+            targets.push(hovered);
+        }
+    }
+    let mut outlines = targets
+        .into_iter()
+        .filter_map(|target| Some((target, outline(queries.q0(), &meshes, target)?)))
+        .collect::<Vec<_>>();
+
+    // Move the existing overlays, remove the ones that are not needed anymore
+    for (entity, highlight, transform, sprite, ui_style) in queries.q1_mut().iter_mut() {
+        let index = outlines
+            .iter()
+            .position(|(target, _)| *target == highlight.target);
+        let outline = index.map(|index| outlines.remove(index).1);
+        match (outline, transform, sprite, ui_style) {
+            (
+                Some(Outline::World {
+                    transform: target_transform,
+                    size,
+                }),
+                Some(mut transform),
+                Some(mut sprite),
+                _,
+            ) => {
+                if *transform != target_transform {
+                    *transform = target_transform;
+                }
+                if sprite.size != size {
+                    sprite.size = size;
+                }
+            }
+            (Some(Outline::Ui { center, size }), _, None, Some(mut ui_style)) => {
+                let target_style = ui_style_for(center, size);
+                if ui_style.position != target_style.position || ui_style.size != target_style.size
+                {
+                    *ui_style = target_style;
+                }
+            }
+            (outline, ..) => {
+                commands.despawn(entity);
+                // The kind of outline changed, a new overlay is needed
+                if let Some(outline) = outline {
+                    outlines.push((highlight.target, outline));
+                }
+            }
+        }
+    }
+
+    for (target, outline) in outlines {
+        match outline {
+            Outline::World { transform, size } => {
+                commands.spawn(SpriteBundle {
+                    sprite: Sprite::new(size),
+                    material: style.color_entity_highlight.clone(),
+                    transform,
+                    visible: Visible {
+                        is_transparent: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+            Outline::Ui { center, size } => {
+                commands.spawn(NodeBundle {
+                    style: ui_style_for(center, size),
+                    material: style.color_entity_highlight.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        commands.with(EntityHighlight { target }).with(DebugIgnore);
+    }
+}
@@ -0,0 +1,449 @@
+use std::ops::Deref;
+
+use bevy::{
+    prelude::*,
+    reflect::TypeRegistry,
+    ui::{self, FocusPolicy},
+};
+
+use crate::{
+    widgets::{ecr_tree, input_box, TextRole},
+    DebugIgnore, Overhead, OverheadPart,
+};
+
+/// Edits made through the debug menu, that can be undone and redone
+#[derive(Debug, Default)]
+pub struct History {
+    changes: Vec<HistoryEntry>,
+    /// Number of changes that are currently applied, the following ones can be redone
+    position: usize,
+    /// Incremented on every modification, used to refresh the History panel
+    revision: usize,
+    /// Undo and redo requested by the buttons and shortcuts, applied by [`apply_system`]
+    requests: Vec<HistoryRequest>,
+}
+
+#[derive(Debug)]
+pub struct HistoryEntry {
+    /// Tree whose widget made the change, None for the changes made outside of the trees
+    pub state_entity: Option<Entity>,
+    pub change: ecr_tree::Change,
+}
+
+impl History {
+    pub fn push(&mut self, state_entity: Option<Entity>, change: ecr_tree::Change) {
+        // A new change discards the changes that were undone
+        self.changes.truncate(self.position);
+        self.changes.push(HistoryEntry {
+            state_entity,
+            change,
+        });
+        self.position = self.changes.len();
+        self.revision += 1;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.changes.iter()
+    }
+
+    /// Whether the change at this index is currently applied
+    pub fn is_applied(&self, index: usize) -> bool {
+        index < self.position
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.changes.len()
+    }
+
+    fn undo(&mut self) {
+        self.requests.push(HistoryRequest::Undo);
+    }
+
+    fn redo(&mut self) {
+        self.requests.push(HistoryRequest::Redo);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryRequest {
+    Undo,
+    Redo,
+}
+
+/// Apply the undo and redo requests, by writing the recorded values at their paths in the world and resources.
+/// The trees that display these values are only refreshed, the values do not need to be displayed.
+pub fn apply_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    let requests = match resources.get_mut::<History>() {
+        Some(mut history) if !history.requests.is_empty() => std::mem::take(&mut history.requests),
+        _ => return,
+    };
+    let type_registry_arc = resources.get::<TypeRegistry>().unwrap().deref().clone();
+    for request in requests {
+        let (index, applied_position, key, value, old_values, state_entity, type_name) = {
+            let history = resources.get::<History>().unwrap();
+            let (index, applied_position) = match request {
+                HistoryRequest::Undo if history.can_undo() => {
+                    (history.position - 1, history.position - 1)
+                }
+                HistoryRequest::Redo if history.can_redo() => {
+                    (history.position, history.position + 1)
+                }
+                _ => continue,
+            };
+            let change = &history.changes[index].change;
+            let (value, old_values) = match request {
+                HistoryRequest::Undo => (
+                    change.old_value.clone_value(),
+                    change
+                        .old_values
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone_value()))
+                        .collect(),
+                ),
+                HistoryRequest::Redo => (change.new_value.clone_value(), Vec::new()),
+            };
+            (
+                index,
+                applied_position,
+                change.key.clone(),
+                value,
+                old_values,
+                history.changes[index].state_entity,
+                change.type_name.clone(),
+            )
+        };
+        if revert(
+            world,
+            resources,
+            &type_registry_arc,
+            state_entity,
+            &key,
+            value,
+            old_values,
+        ) {
+            let mut history = resources.get_mut::<History>().unwrap();
+            trace!("History position {} -> {}", index, applied_position);
+            history.position = applied_position;
+            history.revision += 1;
+        } else {
+            warn!(
+                "Cannot revert change of {}: the value does not exist anymore",
+                type_name
+            );
+        }
+    }
+}
+
+/// Write the value of a change, or the value of each entity when several entities were edited together
+fn revert(
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    state_entity: Option<Entity>,
+    key: &ecr_tree::Key,
+    value: Box<dyn Reflect>,
+    values_per_entity: Vec<(ecr_tree::Key, Box<dyn Reflect>)>,
+) -> bool {
+    // Inspected values are only reachable through the inspector that displays them
+    if let Some(ecr_tree::Key::Inspected) = key.path_root() {
+        return match state_entity.map(|entity| world.get_mut::<ecr_tree::State>(entity)) {
+            Some(Ok(mut state)) => state.revert_leaf(key, value),
+            _ => false,
+        };
+    }
+    let writes = if values_per_entity.is_empty() {
+        vec![(key.clone(), value.clone_value())]
+    } else {
+        values_per_entity
+    };
+    for (key, value) in writes.iter() {
+        if let Err(error) =
+            ecr_tree::ecr::set_reflected_value(world, resources, type_registry_arc, key, &**value)
+        {
+            trace!("Revert failed: {}", error);
+            return false;
+        }
+    }
+    for mut state in world.query_mut::<&mut ecr_tree::State>() {
+        state.refresh_leaf(key, &*value);
+        for (key, value) in writes.iter() {
+            state.refresh_leaf(key, &**value);
+        }
+    }
+    true
+}
+
+/// Collect the changes applied by all ecr_tree widgets
+pub fn record_system(
+    mut history: ResMut<History>,
+    mut query_state: Query<(Entity, &mut ecr_tree::State)>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for (state_entity, mut state) in query_state.iter_mut() {
+        for change in state.take_changes() {
+            history.push(Some(state_entity), change);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryButton {
+    Undo,
+    Redo,
+}
+
+pub fn handle_inputs_system(
+    settings: Res<crate::DebugMenuSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    query_cursor: Query<&input_box::Cursor>,
+    query_button: Query<(&HistoryButton, &Interaction), Mutated<Interaction>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    // Undoing and redoing are modifications too
+    if settings.read_only {
+        return;
+    }
+    for (button, interaction) in query_button.iter() {
+        if *interaction == Interaction::Clicked {
+            match button {
+                HistoryButton::Undo => history.undo(),
+                HistoryButton::Redo => history.redo(),
+            }
+        }
+    }
+
+    if input_box::text_input_focused(&query_cursor) {
+        return;
+    }
+    let ctrl =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+    if ctrl && keyboard_input.just_pressed(KeyCode::Z) {
+        history.undo();
+    } else if ctrl && keyboard_input.just_pressed(KeyCode::Y) {
+        history.redo();
+    }
+}
+
+#[derive(Debug)]
+pub struct HistoryList {
+    style: Style,
+    items: Entity,
+    revision: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub font: Handle<Font>,
+    pub font_size: f32,
+    /// Margin around the texts and the buttons, in pixels
+    pub spacing: f32,
+    pub color_background: Handle<ColorMaterial>,
+    pub color_box: Handle<ColorMaterial>,
+    pub color_button: Handle<ColorMaterial>,
+    pub style_box: ui::Style,
+    pub color_text_applied: Color,
+    pub color_text_undone: Color,
+    /// Color of the texts drawn directly on the background
+    pub color_text: Color,
+}
+
+/// The undo and redo buttons are not shown in read-only mode
+pub fn spawn(commands: &mut Commands, style: &Style, read_only: bool) -> Entity {
+    let mut entity = None;
+    let mut items = None;
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: Rect {
+                        left: Val::Undefined,
+                        top: Val::Px(0.0), // We use this for vertical scrolling
+                        bottom: Val::Undefined,
+                        right: Val::Undefined,
+                    },
+                    size: Size {
+                        width: Val::Percent(100.),
+                        height: Val::Undefined, // Height will grow as needed
+                    },
+                    flex_shrink: 0.,
+                    padding: Rect {
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(Children::default())
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: ui::Style {
+                            flex_shrink: 0.,
+                            ..Default::default()
+                        },
+                        material: style.color_background.clone(),
+                        ..Default::default()
+                    })
+                    .with(DebugIgnore)
+                    .with_children(|parent| {
+                        let buttons: &[(HistoryButton, &str)] = if read_only {
+                            &[]
+                        } else {
+                            &[
+                                (HistoryButton::Undo, "Undo (Ctrl+Z)"),
+                                (HistoryButton::Redo, "Redo (Ctrl+Y)"),
+                            ]
+                        };
+                        for (button, label) in buttons.iter() {
+                            parent
+                                .spawn(ButtonBundle {
+                                    style: ui::Style {
+                                        margin: Rect::all(Val::Px(style.spacing)),
+                                        ..Default::default()
+                                    },
+                                    material: style.color_button.clone(),
+                                    ..Default::default()
+                                })
+                                .with(*button)
+                                .with(DebugIgnore)
+                                .with_children(|parent| {
+                                    parent
+                                        .spawn(TextBundle {
+                                            text: Text::with_section(
+                                                *label,
+                                                TextStyle {
+                                                    font: style.font.clone(),
+                                                    font_size: style.font_size,
+                                                    color: style.color_text_applied,
+                                                },
+                                                TextAlignment::default(),
+                                            ),
+                                            style: ui::Style {
+                                                margin: Rect::all(Val::Px(style.spacing)),
+                                                ..Default::default()
+                                            },
+                                            focus_policy: FocusPolicy::Pass,
+                                            ..Default::default()
+                                        })
+                                        .with(TextRole::Text)
+                                        .with(DebugIgnore);
+                                });
+                        }
+                    })
+                    .spawn(NodeBundle {
+                        style: ui::Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            flex_shrink: 0.,
+                            ..Default::default()
+                        },
+                        material: style.color_background.clone(),
+                        ..Default::default()
+                    })
+                    .with(Children::default())
+                    .with(DebugIgnore);
+                items = parent.current_entity();
+            });
+        entity = parent.current_entity();
+        parent.with(HistoryList {
+            style: style.clone(),
+            items: items.unwrap(),
+            revision: None,
+        });
+    });
+    entity.unwrap()
+}
+
+pub fn update_system(
+    commands: &mut Commands,
+    history: Res<History>,
+    mut query_list: Query<&mut HistoryList>,
+    query_children: Query<&Children>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for mut list in query_list.iter_mut() {
+        if list.revision == Some(history.revision) {
+            continue;
+        }
+        trace!("Rebuilding history list");
+        list.revision = Some(history.revision);
+        if let Ok(items) = query_children.get(list.items) {
+            for item in items.iter() {
+                commands.despawn_recursive(*item);
+            }
+        }
+        // Most recent changes first
+        for (index, entry) in history.iter().enumerate().rev() {
+            let change = &entry.change;
+            let label = format!(
+                "{}: {} -> {}",
+                change.label,
+                ecr_tree::serialize_reflect(&*change.old_value)
+                    .unwrap_or_else(|| change.type_name.clone()),
+                ecr_tree::serialize_reflect(&*change.new_value)
+                    .unwrap_or_else(|| change.type_name.clone()),
+            );
+            let (color, role) = if history.is_applied(index) {
+                (list.style.color_text_applied, TextRole::Text)
+            } else {
+                (list.style.color_text_undone, TextRole::Disabled)
+            };
+            commands.set_current_entity(list.items);
+            commands.with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: list.style.style_box.clone(),
+                        material: list.style.color_box.clone(),
+                        ..Default::default()
+                    })
+                    .with(DebugIgnore)
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle {
+                                text: Text::with_section(
+                                    label,
+                                    TextStyle {
+                                        font: list.style.font.clone(),
+                                        font_size: list.style.font_size,
+                                        color,
+                                    },
+                                    TextAlignment {
+                                        vertical: VerticalAlign::Center,
+                                        horizontal: HorizontalAlign::Left,
+                                    },
+                                ),
+                                style: ui::Style {
+                                    align_self: AlignSelf::FlexStart,
+                                    size: Size {
+                                        width: Val::Undefined,
+                                        height: Val::Px(list.style.font_size),
+                                    },
+                                    flex_shrink: 0.,
+                                    margin: Rect::all(Val::Px(list.style.spacing)),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            })
+                            .with(role)
+                            .with(DebugIgnore);
+                    });
+            });
+        }
+    }
+}
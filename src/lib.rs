@@ -1,11 +1,32 @@
 #![allow(clippy::clippy::type_complexity)]
 
+mod archetype;
+mod component;
 mod debug_menu;
 mod diagnostic;
 mod entity;
+mod gizmo;
+mod highlight;
+mod history;
 mod list;
+mod overhead;
+mod picking;
 mod resource;
 mod scene;
+mod selection;
+mod snapshot;
+mod theme;
+mod ui_inspector;
+mod watch;
 pub mod widgets;
 
 pub use debug_menu::*;
+pub use gizmo::Gizmo;
+pub use history::{History, HistoryEntry};
+pub use picking::{PickedEvent, Picking};
+pub use selection::EntitySelection;
+pub use snapshot::{Snapshot, SnapshotValue, Snapshots};
+pub use theme::Theme;
+pub use ui_inspector::UiInspector;
+pub use watch::WatchList;
+pub use widgets::overhead::{Overhead, OverheadPart};
@@ -1 +1,48 @@
+use bevy::{ecs::Component, prelude::*, ui};
 
+use crate::widgets::{ecr_tree, TextRole};
+use crate::DebugIgnore;
+
+/// Text showing how many types cannot be displayed in a panel
+#[derive(Debug)]
+pub struct UnregisteredSummary;
+
+pub(crate) fn spawn_unregistered_summary(parent: &mut ChildBuilder, style: &ecr_tree::Style) {
+    parent
+        .spawn(TextBundle {
+            text: Text::with_section(
+                String::new(),
+                TextStyle {
+                    font: style.font.clone(),
+                    font_size: style.font_size_small(),
+                    color: style.color_text,
+                },
+                Default::default(),
+            ),
+            style: ui::Style {
+                align_self: AlignSelf::Center,
+                margin: Rect::all(Val::Px(style.spacing)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(TextRole::Title)
+        .with(UnregisteredSummary)
+        .with(DebugIgnore);
+}
+
+pub(crate) fn set_unregistered_summary<P: Component>(world: &mut World, summary: String) {
+    let panels = world
+        .query_filtered::<&Children, With<P>>()
+        .flat_map(|children| children.iter().copied().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    for child in panels {
+        if world.get::<UnregisteredSummary>(child).is_ok() {
+            if let Ok(mut text) = world.get_mut::<Text>(child) {
+                if text.sections[0].value != summary {
+                    text.sections[0].value = summary.clone();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+use crate::{
+    widgets::overhead::{Overhead, OverheadPart},
+    DebugIgnore,
+};
+
+/// Time spent in [`crate::widgets::ecr_tree::ecr::update_system`], in milliseconds
+pub const TREE_UPDATE_TIME: DiagnosticId =
+    DiagnosticId::from_u128(328308727263750822724421519457139590459);
+/// Time spent in the system updating the Diagnostics panel, in milliseconds
+pub const DIAGNOSTICS_UPDATE_TIME: DiagnosticId =
+    DiagnosticId::from_u128(209520170748966765395425162002908065849);
+/// Time spent in the systems of the widgets, in milliseconds
+pub const WIDGETS_TIME: DiagnosticId =
+    DiagnosticId::from_u128(135406562140654609423429666161354792464);
+/// Time spent in the systems of the panels, in milliseconds
+pub const MENU_TIME: DiagnosticId =
+    DiagnosticId::from_u128(241875203367917493184740618190617385316);
+/// Number of entities spawned by the debug menu, marked with [`DebugIgnore`]
+pub const MENU_ENTITY_COUNT: DiagnosticId =
+    DiagnosticId::from_u128(305511102797873824355169937321627490230);
+
+pub fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(TREE_UPDATE_TIME, "debug_menu_tree_ms", 20));
+    diagnostics.add(Diagnostic::new(
+        DIAGNOSTICS_UPDATE_TIME,
+        "debug_menu_diagnostics_ms",
+        20,
+    ));
+    diagnostics.add(Diagnostic::new(WIDGETS_TIME, "debug_menu_widgets_ms", 20));
+    diagnostics.add(Diagnostic::new(MENU_TIME, "debug_menu_panels_ms", 20));
+    diagnostics.add(Diagnostic::new(
+        MENU_ENTITY_COUNT,
+        "debug_menu_entity_count",
+        1,
+    ));
+}
+
+/// Report the measures of the frame, runs after all the systems of the menu
+pub fn diagnostic_system(
+    overhead: Res<Overhead>,
+    mut diagnostics: ResMut<Diagnostics>,
+    query_ignored: Query<(), With<DebugIgnore>>,
+) {
+    diagnostics.add_measurement(TREE_UPDATE_TIME, overhead.take_millis(OverheadPart::Tree));
+    diagnostics.add_measurement(
+        DIAGNOSTICS_UPDATE_TIME,
+        overhead.take_millis(OverheadPart::Diagnostics),
+    );
+    diagnostics.add_measurement(WIDGETS_TIME, overhead.take_millis(OverheadPart::Widgets));
+    diagnostics.add_measurement(MENU_TIME, overhead.take_millis(OverheadPart::Menu));
+    diagnostics.add_measurement(MENU_ENTITY_COUNT, query_ignored.iter().count() as f64);
+}
@@ -0,0 +1,184 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::Camera,
+        mesh::VertexAttributeValues,
+        render_graph::base::camera::{CAMERA_2D, CAMERA_3D},
+    },
+};
+
+use crate::{DebugIgnore, Overhead, OverheadPart};
+
+/// Picking mode, where clicking a sprite or a mesh in the game shows its entity in the Entities panel
+#[derive(Debug, Default)]
+pub struct Picking {
+    pub enabled: bool,
+}
+
+/// Sent when an entity is clicked in picking mode
+#[derive(Debug)]
+pub struct PickedEvent {
+    pub entity: Entity,
+}
+
+impl crate::debug_menu::ToggledMode for Picking {
+    const NAME: &'static str = "Picking";
+    fn key(settings: &crate::DebugMenuSettings) -> KeyCode {
+        settings.picking_key
+    }
+    fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+}
+
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn pick_system(
+    picking: Res<Picking>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    meshes: Res<Assets<Mesh>>,
+    query_camera: Query<(&Camera, &GlobalTransform)>,
+    query_ui: Query<(&Node, &GlobalTransform), With<DebugIgnore>>,
+    query_sprite: Query<(Entity, &Sprite, &GlobalTransform), Without<DebugIgnore>>,
+    query_mesh: Query<(Entity, &Handle<Mesh>, &GlobalTransform), Without<DebugIgnore>>,
+    mut picked_events: ResMut<Events<PickedEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if !picking.enabled || !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = match window.cursor_position() {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    // Clicks on the debug menu are not picking anything
+    if query_ui
+        .iter()
+        .any(|(node, transform)| ui_contains(node, transform, cursor))
+    {
+        return;
+    }
+
+    let mut nearest: Option<(f32, Entity)> = None;
+    for (camera, camera_transform) in query_camera.iter() {
+        let ray = match cursor_ray(window, cursor, camera, camera_transform) {
+            Some(ray) => ray,
+            None => continue,
+        };
+
+        let mut hit = |entity: Entity, transform: &GlobalTransform, min: Vec3, max: Vec3| {
+            if let Some(distance) = intersect(ray, transform, min, max) {
+                if nearest.map_or(true, |(nearest, _)| distance < nearest) {
+                    nearest = Some((distance, entity));
+                }
+            }
+        };
+        for (entity, sprite, transform) in query_sprite.iter() {
+            let half_size = (sprite.size / 2.).extend(0.);
+            hit(entity, transform, -half_size, half_size);
+        }
+        for (entity, mesh, transform) in query_mesh.iter() {
+            if let Some((min, max)) = meshes.get(mesh).and_then(mesh_bounds) {
+                hit(entity, transform, min, max);
+            }
+        }
+    }
+    if let Some((_, entity)) = nearest {
+        trace!("Entity picked: {:?}", entity);
+        picked_events.send(PickedEvent { entity });
+    }
+}
+
+/// Ray going through the cursor from the near plane of a camera, as an origin and a direction.
+/// Returns None for the UI camera, which does not render the game world.
+pub(crate) fn cursor_ray(
+    window: &Window,
+    cursor: Vec2,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<(Vec3, Vec3)> {
+    if !matches!(camera.name.as_deref(), Some(CAMERA_2D) | Some(CAMERA_3D)) {
+        return None;
+    }
+    let ndc = Vec2::new(
+        cursor.x / window.width() * 2. - 1.,
+        cursor.y / window.height() * 2. - 1.,
+    );
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix.inverse();
+    let near = project(&ndc_to_world, ndc.extend(0.));
+    let far = project(&ndc_to_world, ndc.extend(1.));
+    Some((near, far - near))
+}
+
+/// UI positions are the center of the nodes, in pixels from the bottom left of the window
+pub(crate) fn ui_contains(node: &Node, transform: &GlobalTransform, position: Vec2) -> bool {
+    let center = transform.translation.truncate();
+    let half_size = node.size / 2.;
+    let min = center - half_size;
+    let max = center + half_size;
+    position.x >= min.x && position.x <= max.x && position.y >= min.y && position.y <= max.y
+}
+
+fn project(matrix: &Mat4, point: Vec3) -> Vec3 {
+    let point = *matrix * point.extend(1.);
+    point.truncate() / point.w
+}
+
+/// Distance along the ray to the bounding box of an entity, in units of the ray direction
+fn intersect(
+    (origin, direction): (Vec3, Vec3),
+    transform: &GlobalTransform,
+    min: Vec3,
+    max: Vec3,
+) -> Option<f32> {
+    // The ray is moved to the space of the entity so that the box is aligned with the axes
+    let world_to_local = transform.compute_matrix().inverse();
+    let origin: [f32; 3] = world_to_local.transform_point3(origin).into();
+    let direction: [f32; 3] = world_to_local.transform_vector3(direction).into();
+    let min: [f32; 3] = min.into();
+    let max: [f32; 3] = max.into();
+
+    let mut t_min = 0f32;
+    let mut t_max = f32::INFINITY;
+    let axes = origin
+        .iter()
+        .zip(direction.iter())
+        .zip(min.iter().zip(max.iter()));
+    for ((origin, direction), (min, max)) in axes {
+        if direction.abs() < f32::EPSILON {
+            if origin < min || origin > max {
+                return None;
+            }
+        } else {
+            let t1 = (min - origin) / direction;
+            let t2 = (max - origin) / direction;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+        }
+    }
+    if t_min <= t_max {
+        Some(t_min)
+    } else {
+        None
+    }
+}
+
+/// Bounding box of the vertices of a mesh, in the space of the mesh
+pub(crate) fn mesh_bounds(mesh: &Mesh) -> Option<(Vec3, Vec3)> {
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION)? {
+        VertexAttributeValues::Float3(positions) => positions,
+        _ => return None,
+    };
+    positions
+        .iter()
+        .map(|position| Vec3::from(*position))
+        .fold(None, |bounds, position| match bounds {
+            None => Some((position, position)),
+            Some((min, max)) => Some((min.min(position), max.max(position))),
+        })
+}
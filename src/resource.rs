@@ -4,7 +4,7 @@ use bevy::{prelude::*, ui};
 use bevy::reflect::{ReflectResource, TypeRegistry};
 
 use crate::widgets::ecr_tree;
-use crate::DebugIgnore;
+use crate::{DebugIgnore, Overhead, OverheadPart};
 
 #[cfg(feature = "extra")]
 #[derive(Reflect, Default)]
@@ -37,8 +37,8 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
                     },
                     flex_shrink: 0.,
                     padding: Rect {
-                        left: Val::Px(4.0),
-                        right: Val::Px(4.0),
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
                         top: Val::Px(0.0),
                         bottom: Val::Px(0.0),
                     },
@@ -51,6 +51,8 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
             .with(ResourceList)
             .with(DebugIgnore)
             .with_children(|_parent| {
+                #[cfg(feature = "extra")]
+                crate::list::spawn_unregistered_summary(_parent, style);
                 #[cfg(not(feature = "extra"))]
                 _parent.spawn(TextBundle {
                     text: Text::with_section(
@@ -58,7 +60,7 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
                         TextStyle {
                             font: style.font.clone(),
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: style.color_text,
                         },
                         Default::default(),
                     ),
@@ -77,6 +79,9 @@ pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
 
 #[cfg(feature = "extra")]
 pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
     use std::ops::Deref;
     let type_registry_arc = resources.get::<TypeRegistry>().unwrap().deref().clone();
     let type_registry = type_registry_arc.read();
@@ -92,7 +97,16 @@ pub fn update_system(world: &mut World, resources: &mut Resources) {
             }
         })
         .collect();
-    // TODO: Show count of unregistered resources
+    drop(type_registry);
+    let summary = if unregistered_count > 0 {
+        format!(
+            "{} resource types are not registered, register them with app.register_type",
+            unregistered_count
+        )
+    } else {
+        String::new()
+    };
+    crate::list::set_unregistered_summary::<ResourceList>(world, summary);
 
     for mut state in world.query_filtered_mut::<&mut ecr_tree::State, With<ResourceList>>() {
         let keys = state.get_root_keys_mut();
@@ -6,7 +6,7 @@ use bevy::{
     ui::{self, FocusPolicy},
 };
 
-use crate::DebugIgnore;
+use crate::{widgets::TextRole, DebugIgnore, Overhead, OverheadPart};
 
 #[derive(Debug)]
 pub struct SceneList {
@@ -17,7 +17,11 @@ pub struct SceneList {
 pub struct Style {
     pub font: Handle<Font>,
     pub font_size: f32,
+    /// Margin around the texts and the buttons, in pixels
+    pub spacing: f32,
     pub color_background: Handle<ColorMaterial>,
+    pub color_button: Handle<ColorMaterial>,
+    pub color_text: Color,
 }
 
 pub struct SaveSceneButton;
@@ -41,8 +45,8 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
                     },
                     flex_shrink: 0.,
                     padding: Rect {
-                        left: Val::Px(4.0),
-                        right: Val::Px(4.0),
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
                         top: Val::Px(0.0),
                         bottom: Val::Px(0.0),
                     },
@@ -58,7 +62,10 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
             .with(DebugIgnore)
             .with_children(|parent| {
                 parent
-                    .spawn(ButtonBundle::default())
+                    .spawn(ButtonBundle {
+                        material: style.color_button.clone(),
+                        ..Default::default()
+                    })
                     .with(SaveSceneButton)
                     .with(DebugIgnore)
                     .with_children(|parent| {
@@ -69,13 +76,14 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
                                     TextStyle {
                                         font: style.font.clone(),
                                         font_size: style.font_size,
-                                        color: Color::BLACK,
+                                        color: style.color_text,
                                     },
                                     TextAlignment::default(),
                                 ),
                                 focus_policy: FocusPolicy::Pass,
                                 ..Default::default()
                             })
+                            .with(TextRole::Text)
                             .with(DebugIgnore);
                     });
             });
@@ -85,6 +93,9 @@ pub fn spawn(commands: &mut Commands, style: &Style) -> Entity {
 }
 
 pub fn interact_save_button(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
     for interaction in world.query_filtered::<&Interaction, With<SaveSceneButton>>() {
         if *interaction == Interaction::Clicked {
             let type_registry = resources.get::<TypeRegistry>().unwrap();
@@ -0,0 +1,142 @@
+use bevy::{prelude::*, reflect::TypeRegistry, ui};
+
+use crate::widgets::{
+    ecr_tree::{self, node::SelectEvent},
+    TextRole,
+};
+use crate::{DebugIgnore, Overhead, OverheadPart};
+
+/// Entities selected to be edited together in the Selection panel
+#[derive(Debug, Default)]
+pub struct EntitySelection {
+    pub entities: Vec<Entity>,
+}
+
+#[derive(Debug)]
+pub struct SelectionPanel;
+
+pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
+    let mut entity = None;
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: Rect {
+                        left: Val::Undefined,
+                        top: Val::Px(0.0), // We use this for vertical scrolling
+                        bottom: Val::Undefined,
+                        right: Val::Undefined,
+                    },
+                    size: Size {
+                        width: Val::Percent(100.),
+                        height: Val::Undefined, // Height will grow as needed
+                    },
+                    flex_shrink: 0.,
+                    padding: Rect {
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(Children::default())
+            .with(SelectionPanel)
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle {
+                        text: Text::with_section(
+                            "Select entities to edit the components they share",
+                            TextStyle {
+                                font: style.font.clone(),
+                                font_size: style.font_size_small(),
+                                color: style.color_text,
+                            },
+                            Default::default(),
+                        ),
+                        style: ui::Style {
+                            align_self: AlignSelf::Center,
+                            margin: Rect::all(Val::Px(style.spacing)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(TextRole::Title)
+                    .with(DebugIgnore);
+            });
+        entity = parent.current_entity();
+        parent.with(ecr_tree::State::new(entity.unwrap(), style.clone()));
+    });
+    entity.unwrap()
+}
+
+/// Add or remove entities from the selection
+pub fn select_system(
+    mut select_events: EventReader<SelectEvent>,
+    mut selection: ResMut<EntitySelection>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for event in select_events.iter() {
+        if let Some(index) = selection
+            .entities
+            .iter()
+            .position(|entity| *entity == event.entity)
+        {
+            selection.entities.remove(index);
+        } else {
+            selection.entities.push(event.entity);
+        }
+    }
+}
+
+pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    let mut selection = resources.get_mut::<EntitySelection>().unwrap();
+    // Forget the entities that were despawned
+    selection
+        .entities
+        .retain(|entity| world.get_entity_location(*entity).is_some());
+
+    // Find the reflected components that all the selected entities have
+    let mut shared_types = Vec::new();
+    if let Some(first) = selection.entities.first() {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let type_registry = type_registry.read();
+        let location = world.get_entity_location(*first).unwrap();
+        for type_info in world.archetypes[location.archetype as usize].types() {
+            let is_reflected = type_registry
+                .get(type_info.id())
+                .map(|registration| registration.data::<ReflectComponent>().is_some())
+                .unwrap_or(false);
+            let is_shared = selection.entities.iter().all(|entity| {
+                let location = world.get_entity_location(*entity).unwrap();
+                world.archetypes[location.archetype as usize].has_dynamic(type_info.id())
+            });
+            if is_reflected && is_shared {
+                shared_types.push((type_info.type_name(), type_info.id()));
+            }
+        }
+    }
+    shared_types.sort_unstable();
+    let keys = shared_types
+        .into_iter()
+        .map(|(_, type_id)| ecr_tree::Key::SharedComponent {
+            entities: selection.entities.clone(),
+            type_id,
+        })
+        .collect::<Vec<_>>();
+
+    for mut state in world.query_filtered_mut::<&mut ecr_tree::State, With<SelectionPanel>>() {
+        let root_keys = state.get_root_keys_mut();
+        root_keys.clear();
+        root_keys.extend(keys.iter().cloned());
+    }
+}
@@ -0,0 +1,715 @@
+use std::{any::TypeId, ops::Deref, sync::Arc};
+
+#[cfg(feature = "extra")]
+use bevy::reflect::ReflectResource;
+use bevy::{
+    prelude::*,
+    reflect::{List, Map, ReflectRef, Struct, Tuple, TupleStruct, TypeRegistry},
+    ui::{self, FocusPolicy},
+};
+
+use crate::{
+    history,
+    widgets::{
+        ecr_tree::{self, node::SnapshotEvent},
+        TextRole,
+    },
+    DebugIgnore, Overhead, OverheadPart,
+};
+
+/// Values of the reflected components of an entity, or of a resource, frozen at some point
+#[derive(Debug)]
+pub struct Snapshot {
+    pub id: usize,
+    pub key: ecr_tree::Key,
+    pub label: String,
+    pub values: Vec<SnapshotValue>,
+    /// Changed fields, updated every frame
+    diff: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SnapshotValue {
+    pub type_id: TypeId,
+    pub type_name: String,
+    pub value: Box<dyn Reflect>,
+}
+
+/// Snapshots taken from the other panels, compared to the live values in the Snapshots panel
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    snapshots: Vec<Snapshot>,
+    next_id: usize,
+    /// Snapshot that the other snapshots of the same entity or resource are compared to, instead of the live value
+    compared_with: Option<usize>,
+    requested: Vec<ecr_tree::Key>,
+    restored: Vec<usize>,
+    /// Incremented on every modification, used to refresh the Snapshots panel
+    revision: usize,
+    /// Revision when the snapshots were last compared
+    diffed_revision: Option<usize>,
+}
+
+impl Snapshots {
+    pub fn iter(&self) -> impl Iterator<Item = &Snapshot> {
+        self.snapshots.iter()
+    }
+
+    fn get(&self, id: usize) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.id == id)
+    }
+
+    /// The snapshot `snapshot` is compared to, if it is not compared to the live value
+    fn reference(&self, snapshot: &Snapshot) -> Option<&Snapshot> {
+        self.compared_with
+            .filter(|id| *id != snapshot.id)
+            .and_then(|id| self.get(id))
+            .filter(|reference| reference.key == snapshot.key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotAction {
+    Compare(usize),
+    Restore(usize),
+    Delete(usize),
+}
+
+/// Queue the snapshots requested from the other panels and the actions of the Snapshots panel
+pub fn request_system(
+    settings: Res<crate::DebugMenuSettings>,
+    mut snapshot_events: EventReader<SnapshotEvent>,
+    mut snapshots: ResMut<Snapshots>,
+    query_button: Query<(&SnapshotAction, &Interaction), Mutated<Interaction>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for event in snapshot_events.iter() {
+        snapshots.requested.push(event.key.clone());
+    }
+    for (action, interaction) in query_button.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *action {
+            SnapshotAction::Compare(id) => {
+                snapshots.compared_with = if snapshots.compared_with == Some(id) {
+                    None
+                } else {
+                    Some(id)
+                };
+            }
+            // Restoring is a modification too
+            SnapshotAction::Restore(id) if !settings.read_only => snapshots.restored.push(id),
+            SnapshotAction::Restore(_) => {}
+            SnapshotAction::Delete(id) => {
+                snapshots.snapshots.retain(|snapshot| snapshot.id != id);
+                if snapshots.compared_with == Some(id) {
+                    snapshots.compared_with = None;
+                }
+            }
+        }
+        snapshots.revision += 1;
+    }
+}
+
+/// Take the requested snapshots, restore the requested ones and compare them all
+pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    // Take ownership of the snapshots so that resources is not borrowed during the update
+    let mut snapshots = std::mem::take(&mut *resources.get_mut::<Snapshots>().unwrap());
+    let type_registry_arc = resources.get::<TypeRegistry>().unwrap().deref().clone();
+
+    for key in std::mem::take(&mut snapshots.requested) {
+        if let Some(values) = capture(world, resources, &type_registry_arc, &key) {
+            let seconds = resources.get::<Time>().unwrap().seconds_since_startup();
+            let label = format!(
+                "{} at {:.2}s",
+                key_label(world, &type_registry_arc, &key),
+                seconds
+            );
+            snapshots.snapshots.push(Snapshot {
+                id: snapshots.next_id,
+                key,
+                label,
+                values,
+                diff: Vec::new(),
+            });
+            snapshots.next_id += 1;
+            snapshots.revision += 1;
+        } else {
+            warn!("Cannot take a snapshot of {:?}", key);
+        }
+    }
+
+    for id in std::mem::take(&mut snapshots.restored) {
+        if let Some(snapshot) = snapshots.get(id) {
+            trace!("Restoring snapshot {}", snapshot.label);
+            let changes = restore(world, resources, &type_registry_arc, snapshot);
+            let mut history = resources.get_mut::<history::History>().unwrap();
+            for change in changes {
+                history.push(None, change);
+            }
+        }
+    }
+
+    // The snapshots are only compared while they are displayed, at the refresh rate of the values
+    let displayed = world.query::<&SnapshotList>().next().is_some();
+    let refresh = resources
+        .get::<ecr_tree::LiveUpdates>()
+        .map_or(true, |live_updates| live_updates.is_refreshing());
+    if !displayed || (!refresh && snapshots.diffed_revision == Some(snapshots.revision)) {
+        *resources.get_mut::<Snapshots>().unwrap() = snapshots;
+        return;
+    }
+
+    let mut diffs = Vec::with_capacity(snapshots.snapshots.len());
+    for snapshot in snapshots.snapshots.iter() {
+        let diff = if let Some(reference) = snapshots.reference(snapshot) {
+            diff_snapshot(&reference.values, &snapshot.values)
+        } else if let Some(live) = capture(world, resources, &type_registry_arc, &snapshot.key) {
+            diff_snapshot(&snapshot.values, &live)
+        } else {
+            vec!["not found anymore".to_string()]
+        };
+        diffs.push(diff);
+    }
+    for (snapshot, diff) in snapshots.snapshots.iter_mut().zip(diffs) {
+        if snapshot.diff != diff {
+            snapshot.diff = diff;
+            snapshots.revision += 1;
+        }
+    }
+    snapshots.diffed_revision = Some(snapshots.revision);
+
+    // Put back the snapshots in the resource
+    *resources.get_mut::<Snapshots>().unwrap() = snapshots;
+}
+
+#[cfg_attr(not(feature = "extra"), allow(unused_variables))]
+fn key_label(world: &World, type_registry_arc: &TypeRegistry, key: &ecr_tree::Key) -> String {
+    match key {
+        ecr_tree::Key::Entity { entity } => match world.get::<Name>(*entity) {
+            Ok(name) => format!("{:?} {}", entity, name.as_str()),
+            Err(_) => format!("{:?}", entity),
+        },
+        #[cfg(feature = "extra")]
+        ecr_tree::Key::Resource { type_id } => type_registry_arc
+            .read()
+            .get(*type_id)
+            .map(|registration| registration.short_name().to_string())
+            .unwrap_or_else(|| format!("{:?}", type_id)),
+        _ => format!("{:?}", key),
+    }
+}
+
+/// Clone the reflected values of an entity or a resource, returns None if it does not exist
+#[cfg_attr(not(feature = "extra"), allow(unused_variables))]
+fn capture(
+    world: &World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    key: &ecr_tree::Key,
+) -> Option<Vec<SnapshotValue>> {
+    let type_registry = type_registry_arc.read();
+    match key {
+        ecr_tree::Key::Entity { entity } => {
+            let location = world.get_entity_location(*entity)?;
+            let archetype = &world.archetypes[location.archetype as usize];
+            let mut values = Vec::new();
+            for type_info in archetype.types() {
+                if let Some(registration) = type_registry.get(type_info.id()) {
+                    if let Some(reflect_component) = registration.data::<ReflectComponent>() {
+                        // SAFETY:
+                        //      we just obtained archetype and location from world so they are valid
+                        //      the component is cloned before world is used again
+                        let component = unsafe {
+                            reflect_component.reflect_component(archetype, location.index)
+                        };
+                        values.push(SnapshotValue {
+                            type_id: type_info.id(),
+                            type_name: registration.short_name().to_string(),
+                            value: component.clone_value(),
+                        });
+                    }
+                }
+            }
+            Some(values)
+        }
+        #[cfg(feature = "extra")]
+        ecr_tree::Key::Resource { type_id } => {
+            let registration = type_registry.get(*type_id)?;
+            let reflect_resource = registration.data::<ReflectResource>()?.clone();
+            let type_name = registration.short_name().to_string();
+            drop(type_registry);
+            reflect_resource.borrow_mut_resource(resources);
+            // Safety:
+            //      we just borrowed the reflected resource exclusively
+            //      resources is not used to access the reflected resource until it is released
+            let value = unsafe { reflect_resource.reflect_resource_mut(resources) }.clone_value();
+            // Safety: the reflected resource was borrowed in this function
+            unsafe {
+                reflect_resource.release_mut_resource(resources);
+            }
+            Some(vec![SnapshotValue {
+                type_id: *type_id,
+                type_name,
+                value,
+            }])
+        }
+        _ => None,
+    }
+}
+
+/// Write the values of a snapshot back, returns the changes of the values that existed to record them in the history.
+/// Components removed since the snapshot are inserted again, components added since are kept.
+fn restore(
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    snapshot: &Snapshot,
+) -> Vec<ecr_tree::Change> {
+    let live = capture(world, resources, type_registry_arc, &snapshot.key).unwrap_or_default();
+    let changes = snapshot
+        .values
+        .iter()
+        .filter_map(|value| {
+            let old_value = live.iter().find(|live| live.type_id == value.type_id)?;
+            let root = match snapshot.key {
+                ecr_tree::Key::Entity { entity } => ecr_tree::Key::Component {
+                    entity,
+                    type_id: value.type_id,
+                },
+                ref key => key.clone(),
+            };
+            Some(ecr_tree::Change {
+                key: ecr_tree::Key::ReflectNode {
+                    path: ecr_tree::ReflectPath {
+                        root: Arc::new(root),
+                        segments: Vec::new(),
+                    },
+                    type_id: value.type_id,
+                    variant_index: None,
+                },
+                label: format!("{} {}", snapshot.label, value.type_name),
+                type_name: value.type_name.clone(),
+                old_value: old_value.value.clone_value(),
+                new_value: value.value.clone_value(),
+                old_values: Vec::new(),
+            })
+        })
+        .collect();
+
+    let type_registry = type_registry_arc.read();
+    match snapshot.key {
+        ecr_tree::Key::Entity { entity } => {
+            let location = match world.get_entity_location(entity) {
+                Some(location) => location,
+                None => {
+                    warn!(
+                        "Cannot restore {}: the entity was despawned",
+                        snapshot.label
+                    );
+                    return Vec::new();
+                }
+            };
+            let live_types = world.archetypes[location.archetype as usize]
+                .types()
+                .iter()
+                .map(|type_info| type_info.id())
+                .collect::<Vec<_>>();
+            for value in snapshot.values.iter() {
+                if let Some(reflect_component) = type_registry
+                    .get(value.type_id)
+                    .and_then(|registration| registration.data::<ReflectComponent>())
+                {
+                    if live_types.contains(&value.type_id) {
+                        reflect_component.apply_component(world, entity, &*value.value);
+                    } else {
+                        reflect_component.add_component(world, resources, entity, &*value.value);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "extra")]
+        ecr_tree::Key::Resource { type_id } => {
+            if let Some(reflect_resource) = type_registry
+                .get(type_id)
+                .and_then(|registration| registration.data::<ReflectResource>())
+                .cloned()
+            {
+                drop(type_registry);
+                reflect_resource.borrow_mut_resource(resources);
+                // Safety: same as in capture()
+                let resource = unsafe { reflect_resource.reflect_resource_mut(resources) };
+                for value in snapshot.values.iter() {
+                    resource.apply(&*value.value);
+                }
+                resources.set_mutated_dynamic(&type_id);
+                // Safety: the reflected resource was borrowed in this function
+                unsafe {
+                    reflect_resource.release_mut_resource(resources);
+                }
+            }
+        }
+        _ => {}
+    }
+    changes
+}
+
+fn diff_snapshot(old: &[SnapshotValue], new: &[SnapshotValue]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for old_value in old.iter() {
+        match new.iter().find(|value| value.type_id == old_value.type_id) {
+            Some(new_value) => {
+                let mut path = old_value.type_name.clone();
+                diff_values(&mut path, &*old_value.value, &*new_value.value, &mut lines);
+            }
+            None => lines.push(format!("{}: removed", old_value.type_name)),
+        }
+    }
+    for new_value in new.iter() {
+        if !old.iter().any(|value| value.type_id == new_value.type_id) {
+            lines.push(format!("{}: added", new_value.type_name));
+        }
+    }
+    lines
+}
+
+/// Push a line for each field that differs, `path` is the field of `old` and `new`
+fn diff_values(path: &mut String, old: &dyn Reflect, new: &dyn Reflect, lines: &mut Vec<String>) {
+    match (old.reflect_ref(), new.reflect_ref()) {
+        (ReflectRef::Struct(old), ReflectRef::Struct(new))
+            if old.field_len() == new.field_len() =>
+        {
+            for index in 0..old.field_len() {
+                let name = old.name_at(index).unwrap();
+                if let Some(new_field) = new.field(name) {
+                    let segment = format!(".{}", name);
+                    diff_field(
+                        path,
+                        segment,
+                        old.field_at(index).unwrap(),
+                        new_field,
+                        lines,
+                    );
+                }
+            }
+        }
+        (ReflectRef::TupleStruct(old), ReflectRef::TupleStruct(new))
+            if old.field_len() == new.field_len() =>
+        {
+            for index in 0..old.field_len() {
+                let (old, new) = (old.field(index).unwrap(), new.field(index).unwrap());
+                diff_field(path, format!(".{}", index), old, new, lines);
+            }
+        }
+        (ReflectRef::Tuple(old), ReflectRef::Tuple(new)) if old.field_len() == new.field_len() => {
+            for index in 0..old.field_len() {
+                let (old, new) = (old.field(index).unwrap(), new.field(index).unwrap());
+                diff_field(path, format!(".{}", index), old, new, lines);
+            }
+        }
+        (ReflectRef::List(old), ReflectRef::List(new)) if old.len() == new.len() => {
+            for index in 0..old.len() {
+                let (old, new) = (old.get(index).unwrap(), new.get(index).unwrap());
+                diff_field(path, format!(".{}", index), old, new, lines);
+            }
+        }
+        (ReflectRef::Map(old), ReflectRef::Map(new)) => {
+            for index in 0..old.len() {
+                let (key, old_value) = old.get_at(index).unwrap();
+                let segment = format!("[{}]", display_value(key));
+                match new.get(key) {
+                    Some(new_value) => diff_field(path, segment, old_value, new_value, lines),
+                    None => lines.push(format!("{}{}: removed", path, segment)),
+                }
+            }
+            for index in 0..new.len() {
+                let (key, _) = new.get_at(index).unwrap();
+                if old.get(key).is_none() {
+                    lines.push(format!("{}[{}]: added", path, display_value(key)));
+                }
+            }
+        }
+        _ => {
+            let equal = old
+                .reflect_partial_eq(new)
+                .unwrap_or_else(|| display_value(old) == display_value(new));
+            if !equal {
+                lines.push(format!(
+                    "{}: {} -> {}",
+                    path,
+                    display_value(old),
+                    display_value(new)
+                ));
+            }
+        }
+    }
+}
+
+fn diff_field(
+    path: &mut String,
+    segment: String,
+    old: &dyn Reflect,
+    new: &dyn Reflect,
+    lines: &mut Vec<String>,
+) {
+    let path_len = path.len();
+    path.push_str(&segment);
+    diff_values(path, old, new, lines);
+    path.truncate(path_len);
+}
+
+fn display_value(reflect: &dyn Reflect) -> String {
+    ecr_tree::serialize_reflect(reflect).unwrap_or_else(|| reflect.type_name().to_string())
+}
+
+#[derive(Debug)]
+pub struct SnapshotList {
+    style: history::Style,
+    items: Entity,
+    revision: Option<usize>,
+    read_only: bool,
+}
+
+/// The restore buttons are not shown in read-only mode
+pub fn spawn(commands: &mut Commands, style: &history::Style, read_only: bool) -> Entity {
+    let mut entity = None;
+    let mut items = None;
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: Rect {
+                        left: Val::Undefined,
+                        top: Val::Px(0.0), // We use this for vertical scrolling
+                        bottom: Val::Undefined,
+                        right: Val::Undefined,
+                    },
+                    size: Size {
+                        width: Val::Percent(100.),
+                        height: Val::Undefined, // Height will grow as needed
+                    },
+                    flex_shrink: 0.,
+                    padding: Rect {
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(Children::default())
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle {
+                        text: Text::with_section(
+                            "Take snapshots of entities or resources to compare them here",
+                            TextStyle {
+                                font: style.font.clone(),
+                                font_size: style.font_size,
+                                color: style.color_text,
+                            },
+                            Default::default(),
+                        ),
+                        style: ui::Style {
+                            align_self: AlignSelf::Center,
+                            margin: Rect::all(Val::Px(style.spacing)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(TextRole::Title)
+                    .with(DebugIgnore)
+                    .spawn(NodeBundle {
+                        style: ui::Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            flex_shrink: 0.,
+                            ..Default::default()
+                        },
+                        material: style.color_background.clone(),
+                        ..Default::default()
+                    })
+                    .with(Children::default())
+                    .with(DebugIgnore);
+                items = parent.current_entity();
+            });
+        entity = parent.current_entity();
+        parent.with(SnapshotList {
+            style: style.clone(),
+            items: items.unwrap(),
+            revision: None,
+            read_only,
+        });
+    });
+    entity.unwrap()
+}
+
+pub fn update_list_system(
+    commands: &mut Commands,
+    snapshots: Res<Snapshots>,
+    mut query_list: Query<&mut SnapshotList>,
+    query_children: Query<&Children>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for mut list in query_list.iter_mut() {
+        if list.revision == Some(snapshots.revision) {
+            continue;
+        }
+        trace!("Rebuilding snapshot list");
+        list.revision = Some(snapshots.revision);
+        if let Ok(items) = query_children.get(list.items) {
+            for item in items.iter() {
+                commands.despawn_recursive(*item);
+            }
+        }
+        // Most recent snapshots first
+        for snapshot in snapshots.iter().rev() {
+            let title = match snapshots.reference(snapshot) {
+                Some(reference) => format!("{} (compared to #{})", snapshot.label, reference.id),
+                None => snapshot.label.clone(),
+            };
+            let compare = if snapshots.compared_with == Some(snapshot.id) {
+                "compared"
+            } else {
+                "compare"
+            };
+            let mut buttons = vec![(SnapshotAction::Compare(snapshot.id), compare)];
+            if !list.read_only {
+                buttons.push((SnapshotAction::Restore(snapshot.id), "restore"));
+            }
+            buttons.push((SnapshotAction::Delete(snapshot.id), "delete"));
+            let lines = if snapshot.diff.is_empty() {
+                vec!["no changes".to_string()]
+            } else {
+                snapshot.diff.clone()
+            };
+
+            let style = &list.style;
+            commands.set_current_entity(list.items);
+            commands.with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: ui::Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            ..style.style_box.clone()
+                        },
+                        material: style.color_box.clone(),
+                        ..Default::default()
+                    })
+                    .with(DebugIgnore)
+                    .with_children(|parent| {
+                        parent
+                            .spawn(NodeBundle {
+                                style: ui::Style {
+                                    flex_shrink: 0.,
+                                    ..Default::default()
+                                },
+                                material: style.color_box.clone(),
+                                ..Default::default()
+                            })
+                            .with(DebugIgnore)
+                            .with_children(|parent| {
+                                spawn_text(
+                                    parent,
+                                    style,
+                                    format!("#{} {}", snapshot.id, title),
+                                    TextRole::Text,
+                                );
+                                for (action, label) in buttons {
+                                    spawn_button(parent, style, action, label);
+                                }
+                            });
+                        for line in lines {
+                            spawn_text(parent, style, line, TextRole::Disabled);
+                        }
+                    });
+            });
+        }
+    }
+}
+
+fn spawn_text(parent: &mut ChildBuilder, style: &history::Style, text: String, role: TextRole) {
+    let color = match role {
+        TextRole::Disabled => style.color_text_undone,
+        _ => style.color_text_applied,
+    };
+    parent
+        .spawn(TextBundle {
+            text: Text::with_section(
+                text,
+                TextStyle {
+                    font: style.font.clone(),
+                    font_size: style.font_size,
+                    color,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Left,
+                },
+            ),
+            style: ui::Style {
+                align_self: AlignSelf::FlexStart,
+                size: Size {
+                    width: Val::Undefined,
+                    height: Val::Px(style.font_size),
+                },
+                flex_shrink: 0.,
+                margin: Rect::all(Val::Px(style.spacing)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(role)
+        .with(DebugIgnore);
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    style: &history::Style,
+    action: SnapshotAction,
+    label: &str,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: ui::Style {
+                margin: Rect::all(Val::Px(style.spacing)),
+                ..Default::default()
+            },
+            material: style.color_button.clone(),
+            ..Default::default()
+        })
+        .with(action)
+        .with(DebugIgnore)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    text: Text::with_section(
+                        label,
+                        TextStyle {
+                            font: style.font.clone(),
+                            font_size: style.font_size,
+                            color: style.color_text_applied,
+                        },
+                        TextAlignment::default(),
+                    ),
+                    style: ui::Style {
+                        margin: Rect::all(Val::Px(style.spacing)),
+                        ..Default::default()
+                    },
+                    focus_policy: FocusPolicy::Pass,
+                    ..Default::default()
+                })
+                .with(TextRole::Text)
+                .with(DebugIgnore);
+        });
+}
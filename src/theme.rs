@@ -0,0 +1,310 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    ui,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostic,
+    widgets::{ecr_tree, TextRole},
+    DebugIgnore, Overhead, OverheadPart,
+};
+
+/// Colors, fonts and sizes from which the [`crate::Style`] of the debug menu is derived.
+/// Set it in [`crate::DebugMenuSettings`], or load it from a `.theme` file written in RON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "4b0e5f1c-7d2a-4a8e-9c3b-6f1d2e8a9b70"]
+pub struct Theme {
+    /// Path of a font in the assets folder, the font embedded in the crate is used with None
+    pub font: Option<String>,
+    /// Monospace font of the Diagnostics panel
+    pub font_mono: Option<String>,
+    pub font_size: f32,
+    /// Space between the rows of the panels and around their texts, in pixels
+    pub spacing: f32,
+    /// Indentation of the children of a node, in pixels
+    pub indent: f32,
+    pub color_background: Color,
+    /// Texts drawn directly on the background
+    pub color_title_text: Color,
+    /// Texts drawn on the buttons and on the boxes
+    pub color_text: Color,
+    /// Undone edits in the History panel
+    pub color_text_disabled: Color,
+    pub color_button: Color,
+    pub color_button_hovered: Color,
+    pub color_button_clicked: Color,
+    pub color_children_container: Color,
+    pub color_input_box: Color,
+    pub color_input_text: Color,
+    /// Values that were just modified outside of the menu
+    pub color_highlight: Color,
+    /// Overlay on the entities of the game that are hovered or selected in the menu
+    pub color_entity_highlight: Color,
+    pub color_scrollbar: Color,
+    pub color_scrollbar_thumb: Color,
+    pub color_gizmo_translate: Color,
+    pub color_gizmo_translate_x: Color,
+    pub color_gizmo_translate_y: Color,
+    pub color_gizmo_rotate: Color,
+    pub color_gizmo_scale: Color,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            font: None,
+            font_mono: None,
+            font_size: 18.0,
+            spacing: 4.0,
+            indent: 8.0,
+            color_background: Color::rgb(0.8, 0.8, 0.8),
+            color_title_text: Color::BLACK,
+            color_text: Color::WHITE,
+            color_text_disabled: Color::GRAY,
+            color_button: Color::BLACK,
+            color_button_hovered: Color::MIDNIGHT_BLUE,
+            color_button_clicked: Color::ALICE_BLUE,
+            color_children_container: Color::rgba(0.2, 0.2, 0.2, 0.8),
+            color_input_box: Color::WHITE,
+            color_input_text: Color::BLACK,
+            color_highlight: Color::rgb(1.0, 0.8, 0.2),
+            color_entity_highlight: Color::rgba(1.0, 0.8, 0.2, 0.3),
+            color_scrollbar: Color::rgb(0.7, 0.7, 0.7),
+            color_scrollbar_thumb: Color::rgb(0.4, 0.4, 0.4),
+            color_gizmo_translate: Color::rgb(1.0, 1.0, 1.0),
+            color_gizmo_translate_x: Color::rgb(0.9, 0.2, 0.2),
+            color_gizmo_translate_y: Color::rgb(0.2, 0.8, 0.2),
+            color_gizmo_rotate: Color::rgb(0.3, 0.4, 1.0),
+            color_gizmo_scale: Color::rgb(1.0, 0.8, 0.2),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            color_background: Color::rgb(0.12, 0.12, 0.14),
+            color_title_text: Color::rgb(0.85, 0.85, 0.85),
+            color_text: Color::rgb(0.9, 0.9, 0.9),
+            color_text_disabled: Color::rgb(0.5, 0.5, 0.5),
+            color_button: Color::rgb(0.22, 0.22, 0.25),
+            color_button_hovered: Color::rgb(0.25, 0.32, 0.5),
+            color_button_clicked: Color::rgb(0.4, 0.5, 0.75),
+            color_children_container: Color::rgba(0.3, 0.3, 0.33, 0.5),
+            color_input_box: Color::rgb(0.05, 0.05, 0.06),
+            color_input_text: Color::rgb(0.9, 0.9, 0.9),
+            color_scrollbar: Color::rgb(0.2, 0.2, 0.22),
+            color_scrollbar_thumb: Color::rgb(0.45, 0.45, 0.5),
+            ..Self::light()
+        }
+    }
+
+    pub fn text_color(&self, role: TextRole) -> Color {
+        match role {
+            TextRole::Title => self.color_title_text,
+            TextRole::Text => self.color_text,
+            TextRole::Disabled => self.color_text_disabled,
+            TextRole::Input => self.color_input_text,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+/// Loads the `.theme` files, containing a [`Theme`] serialized in RON
+#[derive(Debug, Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let theme = ron::de::from_bytes::<Theme>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme"]
+    }
+}
+
+/// Materials of the style, one per color of the theme, recolored in place when the theme changes
+#[derive(Debug, Clone)]
+pub(crate) struct Materials {
+    pub background: Handle<ColorMaterial>,
+    pub button: Handle<ColorMaterial>,
+    pub button_hovered: Handle<ColorMaterial>,
+    pub button_clicked: Handle<ColorMaterial>,
+    pub children_container: Handle<ColorMaterial>,
+    pub input_box: Handle<ColorMaterial>,
+    pub entity_highlight: Handle<ColorMaterial>,
+    pub scrollbar: Handle<ColorMaterial>,
+    pub scrollbar_thumb: Handle<ColorMaterial>,
+    pub gizmo_translate: Handle<ColorMaterial>,
+    pub gizmo_translate_x: Handle<ColorMaterial>,
+    pub gizmo_translate_y: Handle<ColorMaterial>,
+    pub gizmo_rotate: Handle<ColorMaterial>,
+    pub gizmo_scale: Handle<ColorMaterial>,
+}
+
+impl Materials {
+    pub fn new(theme: &Theme, materials: &mut Assets<ColorMaterial>) -> Self {
+        let mut add = |color: Color| materials.add(color.into());
+        Self {
+            background: add(theme.color_background),
+            button: add(theme.color_button),
+            button_hovered: add(theme.color_button_hovered),
+            button_clicked: add(theme.color_button_clicked),
+            children_container: add(theme.color_children_container),
+            input_box: add(theme.color_input_box),
+            entity_highlight: add(theme.color_entity_highlight),
+            scrollbar: add(theme.color_scrollbar),
+            scrollbar_thumb: add(theme.color_scrollbar_thumb),
+            gizmo_translate: add(theme.color_gizmo_translate),
+            gizmo_translate_x: add(theme.color_gizmo_translate_x),
+            gizmo_translate_y: add(theme.color_gizmo_translate_y),
+            gizmo_rotate: add(theme.color_gizmo_rotate),
+            gizmo_scale: add(theme.color_gizmo_scale),
+        }
+    }
+
+    pub fn set_colors(&self, theme: &Theme, materials: &mut Assets<ColorMaterial>) {
+        for (handle, color) in [
+            (&self.background, theme.color_background),
+            (&self.button, theme.color_button),
+            (&self.button_hovered, theme.color_button_hovered),
+            (&self.button_clicked, theme.color_button_clicked),
+            (&self.children_container, theme.color_children_container),
+            (&self.input_box, theme.color_input_box),
+            (&self.entity_highlight, theme.color_entity_highlight),
+            (&self.scrollbar, theme.color_scrollbar),
+            (&self.scrollbar_thumb, theme.color_scrollbar_thumb),
+            (&self.gizmo_translate, theme.color_gizmo_translate),
+            (&self.gizmo_translate_x, theme.color_gizmo_translate_x),
+            (&self.gizmo_translate_y, theme.color_gizmo_translate_y),
+            (&self.gizmo_rotate, theme.color_gizmo_rotate),
+            (&self.gizmo_scale, theme.color_gizmo_scale),
+        ]
+        .iter()
+        {
+            if let Some(material) = materials.get_mut(*handle) {
+                material.color = *color;
+            }
+        }
+    }
+}
+
+/// Theme file loaded from `DebugMenuSettings::theme_path`
+#[derive(Debug, Default)]
+pub struct ThemeFile {
+    handle: Option<Handle<Theme>>,
+}
+
+pub fn load_system(
+    settings: Res<crate::DebugMenuSettings>,
+    asset_server: Res<AssetServer>,
+    mut theme_file: ResMut<ThemeFile>,
+) {
+    if let Some(path) = &settings.theme_path {
+        trace!("Loading debug menu theme {}", path);
+        theme_file.handle = Some(asset_server.load(path.as_str()));
+    }
+}
+
+/// Copy the theme file to the settings once it is loaded, and every time it is modified when the assets are watched
+pub fn reload_system(
+    theme_file: Res<ThemeFile>,
+    themes: Res<Assets<Theme>>,
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    mut settings: ResMut<crate::DebugMenuSettings>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let handle = match &theme_file.handle {
+        Some(handle) => handle,
+        None => return,
+    };
+    for event in theme_events.iter() {
+        match event {
+            AssetEvent::Created { handle: loaded } | AssetEvent::Modified { handle: loaded }
+                if loaded == handle =>
+            {
+                if let Some(theme) = themes.get(handle) {
+                    if settings.theme != *theme {
+                        info!("Debug menu theme reloaded");
+                        settings.theme = theme.clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Derive the style again when the theme of the settings changes, and restyle the widgets already spawned.
+/// The colors of the materials change in place and the texts are matched by their previous font.
+/// The texts that have a [`TextRole`] take its color, and their size is scaled like the font size of the theme,
+/// as the sizes of the texts of the panels are derived from it. Their heights are scaled with them.
+/// The new spacing only applies to the widgets spawned afterwards.
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn apply_system(
+    settings: Res<crate::DebugMenuSettings>,
+    asset_server: Res<AssetServer>,
+    mut style: ResMut<crate::Style>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut query_text: Query<(&mut Text, &mut ui::Style, Option<&TextRole>), With<DebugIgnore>>,
+    mut query_state: Query<&mut ecr_tree::State, With<DebugIgnore>>,
+    mut query_diagnostic: Query<&mut diagnostic::DiagnosticList>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    if settings.theme == *style.theme() {
+        return;
+    }
+    trace!("Applying debug menu theme");
+    let new_style = style.with_theme(&settings.theme, &mut materials, &asset_server);
+
+    let font_scale = settings.theme.font_size / style.theme().font_size;
+    let fonts = [
+        (&style.font, &new_style.font),
+        (
+            &style.style_diagnostic.font,
+            &new_style.style_diagnostic.font,
+        ),
+    ];
+    for (mut text, mut text_style, role) in query_text.iter_mut() {
+        if role.is_some() {
+            // The heights of the texts are their font size
+            if let Val::Px(height) = &mut text_style.size.height {
+                *height *= font_scale;
+            }
+        }
+        for section in text.sections.iter_mut() {
+            if let Some(role) = role {
+                section.style.color = settings.theme.text_color(*role);
+                section.style.font_size *= font_scale;
+            }
+            if let Some((_, font)) = fonts.iter().find(|(old, _)| **old == section.style.font) {
+                section.style.font = (*font).clone();
+            }
+        }
+    }
+    for mut state in query_state.iter_mut() {
+        state.set_style(new_style.style_list.clone());
+    }
+    for mut list in query_diagnostic.iter_mut() {
+        list.style = new_style.style_diagnostic.clone();
+    }
+    *style = new_style;
+}
@@ -0,0 +1,205 @@
+use bevy::{prelude::*, ui};
+
+use crate::{picking, widgets::TextRole, DebugIgnore, Overhead, OverheadPart, PickedEvent};
+
+/// UI inspection mode, where hovering a node of the game UI outlines it and shows its layout
+#[derive(Debug, Default)]
+pub struct UiInspector {
+    pub enabled: bool,
+    hovered: Option<Entity>,
+    /// Box showing the size and the style of the hovered node
+    label: Option<Entity>,
+}
+
+impl UiInspector {
+    /// Node of the game UI under the cursor
+    pub fn hovered(&self) -> Option<Entity> {
+        self.hovered
+    }
+}
+
+impl crate::debug_menu::ToggledMode for UiInspector {
+    const NAME: &'static str = "UI inspection";
+    fn key(settings: &crate::DebugMenuSettings) -> KeyCode {
+        settings.ui_inspector_key
+    }
+    fn enabled_mut(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+}
+
+/// Find the deepest node of the game UI under the cursor, clicking it shows its entity in the Entities panel
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn hover_system(
+    mut inspector: ResMut<UiInspector>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    query_node: Query<(Entity, &Node, &GlobalTransform)>,
+    query_parent: Query<&Parent>,
+    query_ignore: Query<(), With<DebugIgnore>>,
+    mut picked_events: ResMut<Events<PickedEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let cursor = windows
+        .get_primary()
+        .and_then(|window| window.cursor_position());
+    let hovered = match (inspector.enabled, cursor) {
+        (true, Some(cursor)) => query_node
+            .iter()
+            .filter(|(_, node, transform)| picking::ui_contains(node, transform, cursor))
+            .filter_map(|(entity, ..)| Some((depth(&query_parent, &query_ignore, entity)?, entity)))
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, entity)| entity),
+        _ => None,
+    };
+    if inspector.hovered != hovered {
+        inspector.hovered = hovered;
+    }
+    if let Some(entity) = hovered {
+        if mouse_input.just_pressed(MouseButton::Left) {
+            trace!("UI node inspected: {:?}", entity);
+            picked_events.send(PickedEvent { entity });
+        }
+    }
+}
+
+/// Depth of a node in the UI hierarchy, None if it is part of the debug menu
+fn depth(
+    query_parent: &Query<&Parent>,
+    query_ignore: &Query<(), With<DebugIgnore>>,
+    mut entity: Entity,
+) -> Option<usize> {
+    let mut depth = 0;
+    loop {
+        if query_ignore.get(entity).is_ok() {
+            return None;
+        }
+        match query_parent.get(entity) {
+            Ok(parent) => {
+                entity = parent.0;
+                depth += 1;
+            }
+            Err(_) => return Some(depth),
+        }
+    }
+}
+
+/// Show the computed size and the flex properties of the hovered node in the bottom left corner
+pub fn update_label_system(
+    commands: &mut Commands,
+    style: Res<crate::Style>,
+    mut inspector: ResMut<UiInspector>,
+    query_node: Query<(&Node, &ui::Style), Without<DebugIgnore>>,
+    query_children: Query<&Children>,
+    mut query_text: Query<&mut Text>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    let description = inspector
+        .hovered
+        .and_then(|entity| Some((entity, query_node.get(entity).ok()?)))
+        .map(|(entity, (node, ui_style))| describe(entity, node, ui_style));
+    let description = match (description, inspector.label) {
+        (Some(description), _) => description,
+        (None, Some(label)) => {
+            commands.despawn_recursive(label);
+            inspector.label = None;
+            return;
+        }
+        (None, None) => return,
+    };
+
+    let label = match inspector.label {
+        Some(label) => label,
+        None => {
+            let style = &style.style_history;
+            commands
+                .spawn(NodeBundle {
+                    style: ui::Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            left: Val::Px(0.0),
+                            bottom: Val::Px(0.0),
+                            ..Default::default()
+                        },
+                        padding: Rect::all(Val::Px(style.spacing)),
+                        ..Default::default()
+                    },
+                    material: style.color_background.clone(),
+                    ..Default::default()
+                })
+                .with(DebugIgnore)
+                .with_children(|parent| {
+                    parent
+                        .spawn(TextBundle {
+                            text: Text::with_section(
+                                "",
+                                TextStyle {
+                                    font: style.font.clone(),
+                                    font_size: style.font_size,
+                                    color: style.color_text,
+                                },
+                                TextAlignment::default(),
+                            ),
+                            ..Default::default()
+                        })
+                        .with(TextRole::Title)
+                        .with(DebugIgnore);
+                });
+            let label = commands.current_entity().unwrap();
+            inspector.label = Some(label);
+            // The text is filled on the next frame, once the children are inserted
+            return;
+        }
+    };
+    if let Some(text) = query_children
+        .get(label)
+        .ok()
+        .and_then(|children| children.first().copied())
+    {
+        if let Ok(mut text) = query_text.get_mut(text) {
+            if text.sections[0].value != description {
+                text.sections[0].value = description;
+            }
+        }
+    }
+}
+
+fn describe(entity: Entity, node: &Node, style: &ui::Style) -> String {
+    let mut lines = vec![
+        format!("{:?}", entity),
+        format!("size: {:.1} x {:.1}", node.size.x, node.size.y),
+        format!(
+            "display: {:?}, position_type: {:?}",
+            style.display, style.position_type
+        ),
+        format!(
+            "flex_direction: {:?}, flex_wrap: {:?}",
+            style.flex_direction, style.flex_wrap
+        ),
+        format!(
+            "flex_grow: {}, flex_shrink: {}, flex_basis: {:?}",
+            style.flex_grow, style.flex_shrink, style.flex_basis
+        ),
+        format!(
+            "align_items: {:?}, align_self: {:?}, align_content: {:?}",
+            style.align_items, style.align_self, style.align_content
+        ),
+        format!("justify_content: {:?}", style.justify_content),
+        format!(
+            "size: {:?}, min_size: {:?}, max_size: {:?}",
+            style.size, style.min_size, style.max_size
+        ),
+        format!("margin: {:?}", style.margin),
+        format!("padding: {:?}", style.padding),
+        format!("border: {:?}", style.border),
+    ];
+    if style.position_type == PositionType::Absolute {
+        lines.push(format!("position: {:?}", style.position));
+    }
+    if let Some(aspect_ratio) = style.aspect_ratio {
+        lines.push(format!("aspect_ratio: {}", aspect_ratio));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,184 @@
+use bevy::{prelude::*, ui};
+
+use crate::widgets::{
+    ecr_tree::{
+        self,
+        node::{PinButton, PinEvent},
+    },
+    TextRole,
+};
+use crate::{DebugIgnore, Overhead, OverheadPart};
+
+/// Nodes pinned from the other panels, shown as roots of the Watch panel
+#[derive(Debug, Default)]
+pub struct WatchList {
+    pub keys: Vec<ecr_tree::Key>,
+}
+
+#[derive(Debug)]
+pub struct WatchPanel;
+
+pub fn spawn(commands: &mut Commands, style: &ecr_tree::Style) -> Entity {
+    let mut entity = None;
+    commands.with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: Rect {
+                        left: Val::Undefined,
+                        top: Val::Px(0.0), // We use this for vertical scrolling
+                        bottom: Val::Undefined,
+                        right: Val::Undefined,
+                    },
+                    size: Size {
+                        width: Val::Percent(100.),
+                        height: Val::Undefined, // Height will grow as needed
+                    },
+                    flex_shrink: 0.,
+                    padding: Rect {
+                        left: Val::Px(style.spacing),
+                        right: Val::Px(style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(Children::default())
+            .with(WatchPanel)
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle {
+                        text: Text::with_section(
+                            "Pin entities, components, resources or their fields to watch them here",
+                            TextStyle {
+                                font: style.font.clone(),
+                                font_size: style.font_size_small(),
+                                color: style.color_text,
+                            },
+                            Default::default(),
+                        ),
+                        style: ui::Style {
+                            align_self: AlignSelf::Center,
+                            margin: Rect::all(Val::Px(style.spacing)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(TextRole::Title)
+                    .with(DebugIgnore);
+            });
+        entity = parent.current_entity();
+        parent.with(ecr_tree::State::new(entity.unwrap(), style.clone()));
+    });
+    entity.unwrap()
+}
+
+/// Pin or unpin nodes.
+/// A node cannot be pinned together with a node that contains it, they would share their widgets.
+pub fn pin_system(
+    mut pin_events: EventReader<PinEvent>,
+    mut watch_list: ResMut<WatchList>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for event in pin_events.iter() {
+        if let Some(index) = watch_list.keys.iter().position(|key| *key == event.key) {
+            watch_list.keys.remove(index);
+        } else if let Some(pinned) = watch_list
+            .keys
+            .iter()
+            .find(|key| contains(key, &event.key) || contains(&event.key, key))
+        {
+            warn!("Cannot pin {:?}, {:?} is already pinned", event.key, pinned);
+        } else {
+            watch_list.keys.push(event.key.clone());
+        }
+    }
+}
+
+/// Whether the node of the inner key is displayed inside the node of the outer key
+fn contains(outer: &ecr_tree::Key, inner: &ecr_tree::Key) -> bool {
+    match (outer, inner) {
+        (
+            ecr_tree::Key::Entity { entity },
+            ecr_tree::Key::Component {
+                entity: component_entity,
+                ..
+            },
+        ) => entity == component_entity,
+        (
+            ecr_tree::Key::ReflectNode { path: outer, .. },
+            ecr_tree::Key::ReflectNode { path, .. },
+        ) => {
+            outer.root == path.root
+                && outer.segments.len() < path.segments.len()
+                && path.segments.starts_with(&outer.segments)
+        }
+        (_, ecr_tree::Key::ReflectNode { path, .. }) => {
+            *path.root == *outer || contains(outer, &path.root)
+        }
+        _ => false,
+    }
+}
+
+/// Label the pin buttons "unpin" when their node is already in the watch list
+pub fn pin_label_system(
+    watch_list: Res<WatchList>,
+    query_button: Query<(&PinButton, &Children)>,
+    mut query_text: Query<&mut Text>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Menu);
+    for (pin, children) in query_button.iter() {
+        let label = if watch_list.keys.contains(pin.key()) {
+            "unpin"
+        } else {
+            "pin"
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = query_text.get_mut(*child) {
+                // Only modify the text when needed, it would be laid out again
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.to_string();
+                }
+            }
+        }
+    }
+}
+
+fn key_exists(world: &World, key: &ecr_tree::Key) -> bool {
+    match key {
+        ecr_tree::Key::Entity { entity } => world.get_entity_location(*entity).is_some(),
+        ecr_tree::Key::Component { entity, type_id } => world
+            .get_entity_location(*entity)
+            .map(|location| {
+                world.archetypes[location.archetype as usize]
+                    .types()
+                    .iter()
+                    .any(|type_info| type_info.id() == *type_id)
+            })
+            .unwrap_or(false),
+        ecr_tree::Key::ReflectNode { path, .. } => key_exists(world, &path.root),
+        _ => true,
+    }
+}
+
+pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Menu));
+    let mut watch_list = resources.get_mut::<WatchList>().unwrap();
+    // Forget the entities and components that were removed
+    watch_list.keys.retain(|key| key_exists(world, key));
+
+    for mut state in world.query_filtered_mut::<&mut ecr_tree::State, With<WatchPanel>>() {
+        let keys = state.get_root_keys_mut();
+        keys.clear();
+        keys.extend(watch_list.keys.iter().cloned());
+    }
+}
@@ -1,9 +1,13 @@
 use bevy::{prelude::*, ui};
 
+use super::overhead::{Overhead, OverheadPart};
+
 #[derive(Debug)]
 pub struct Widget {
     pub style: Style,
     checked: bool,
+    /// Indeterminate state, when the check box stands for several values that differ
+    mixed: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -12,6 +16,10 @@ pub struct Style {
     pub icon_toggle_off: Handle<ColorMaterial>,
     pub icon_toggle_on_hovered: Option<Handle<ColorMaterial>>,
     pub icon_toggle_off_hovered: Option<Handle<ColorMaterial>>,
+    /// Shown in the indeterminate state, the icon of the current value is used with None
+    pub icon_toggle_mixed: Option<Handle<ColorMaterial>>,
+    /// Horizontal margin around the box, in pixels
+    pub spacing: f32,
 }
 
 pub struct Builder {
@@ -46,8 +54,8 @@ impl BuildCheckBox for Commands {
                             height: Val::Undefined,
                         },
                         margin: Rect {
-                            left: Val::Px(5.0),
-                            right: Val::Px(5.0),
+                            left: Val::Px(style.spacing),
+                            right: Val::Px(style.spacing),
                             ..Default::default()
                         },
                         ..Default::default()
@@ -59,7 +67,11 @@ impl BuildCheckBox for Commands {
                     },
                     ..Default::default()
                 })
-                .with(Widget { checked, style })
+                .with(Widget {
+                    checked,
+                    mixed: false,
+                    style,
+                })
                 .with(Interaction::default())
                 .current_entity();
             if let Some(build_fn) = build_fn.as_ref() {
@@ -79,8 +91,16 @@ impl Widget {
     pub fn is_checked(&self) -> bool {
         self.checked
     }
+    pub fn set_mixed(&mut self, mixed: bool) {
+        self.mixed = mixed;
+    }
+    pub fn is_mixed(&self) -> bool {
+        self.mixed
+    }
+    /// Toggling a check box in the indeterminate state checks it
     pub fn toggle(&mut self) -> bool {
-        self.checked = !self.checked;
+        self.checked = self.mixed || !self.checked;
+        self.mixed = false;
         self.checked
     }
 }
@@ -96,9 +116,16 @@ pub fn update_mutated_system(
         (&Widget, &mut Handle<ColorMaterial>, Option<&Interaction>),
         (With<Widget>, Or<(Mutated<Widget>, Mutated<Interaction>)>),
     >,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (checkbox, mut material, interaction) in query_mutated.iter_mut() {
-        if checkbox.is_checked() {
+        if let (true, Some(icon_toggle_mixed)) = (
+            checkbox.is_mixed(),
+            checkbox.style.icon_toggle_mixed.as_ref(),
+        ) {
+            *material = icon_toggle_mixed.clone();
+        } else if checkbox.is_checked() {
             if interaction == Some(&Interaction::Hovered)
                 && checkbox.style.icon_toggle_on_hovered.is_some()
             {
@@ -118,8 +145,13 @@ pub fn update_mutated_system(
 
 pub fn interact_system(
     mut toggled_events: ResMut<Events<ToggledEvent>>,
-    mut query_toggled: Query<(Entity, &mut Widget, &Interaction), Mutated<Interaction>>,
+    mut query_toggled: Query<
+        (Entity, &mut Widget, &Interaction),
+        (Mutated<Interaction>, Without<super::Disabled>),
+    >,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (entity, mut checkbox, interaction) in query_toggled.iter_mut() {
         if *interaction == Interaction::Clicked {
             toggled_events.send(ToggledEvent {
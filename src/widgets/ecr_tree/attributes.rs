@@ -0,0 +1,158 @@
+use std::any::TypeId;
+
+use bevy::{
+    prelude::{AppBuilder, Reflect},
+    reflect::{GetTypeRegistration, TypeRegistry},
+    utils::HashMap,
+};
+
+/// Display options for the fields of a struct, stored as type data in the [`TypeRegistry`]
+#[derive(Debug, Clone, Default)]
+pub struct FieldAttributes {
+    fields: HashMap<String, FieldAttribute>,
+}
+
+impl FieldAttributes {
+    pub fn with(mut self, field: &str, attribute: FieldAttribute) -> Self {
+        self.fields.insert(field.to_string(), attribute);
+        self
+    }
+    pub fn get(&self, field: &str) -> Option<&FieldAttribute> {
+        self.fields.get(field)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FieldAttribute {
+    /// The field and its content cannot be edited
+    pub read_only: bool,
+    /// The field is not displayed
+    pub hidden: bool,
+    /// Numbers in the field are clamped when edited
+    pub range: Option<FieldRange>,
+    /// Displayed instead of the field name
+    pub display_name: Option<String>,
+    /// Displayed next to the field name when it is hovered
+    pub tooltip: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldRange {
+    pub min: f64,
+    pub max: f64,
+    /// Edited values are rounded to a multiple of the step from `min`
+    pub step: Option<f64>,
+}
+
+impl FieldRange {
+    fn apply(&self, value: f64) -> f64 {
+        let mut value = value.max(self.min).min(self.max);
+        if let Some(step) = self.step.filter(|step| *step > 0.) {
+            value = self.min + ((value - self.min) / step).round() * step;
+            value = value.min(self.max);
+        }
+        value
+    }
+}
+
+impl FieldAttribute {
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+    pub fn range(mut self, min: f64, max: f64, step: Option<f64>) -> Self {
+        self.range = Some(FieldRange { min, max, step });
+        self
+    }
+    pub fn display_name(mut self, display_name: &str) -> Self {
+        self.display_name = Some(display_name.to_string());
+        self
+    }
+    pub fn tooltip(mut self, tooltip: &str) -> Self {
+        self.tooltip = Some(tooltip.to_string());
+        self
+    }
+
+    /// Attribute applied to a field nested in a field with the `parent` attribute
+    pub(super) fn inherit(mut self, parent: &FieldAttribute) -> Self {
+        self.read_only |= parent.read_only;
+        self.range = self.range.or(parent.range);
+        self
+    }
+
+    /// Attribute of a named field of a struct, inherited from the attribute of the struct
+    pub(crate) fn of_field(
+        type_registry: &TypeRegistry,
+        struct_type_id: TypeId,
+        field: &str,
+        parent: &FieldAttribute,
+    ) -> Self {
+        type_registry
+            .read()
+            .get(struct_type_id)
+            .and_then(|registration| registration.data::<FieldAttributes>())
+            .and_then(|field_attributes| field_attributes.get(field))
+            .cloned()
+            .unwrap_or_default()
+            .inherit(parent)
+    }
+
+    /// Attribute of the elements of a tuple, a list, a map or an enum, that do not have their own
+    pub(super) fn of_element(parent: &FieldAttribute) -> Self {
+        FieldAttribute::default().inherit(parent)
+    }
+
+    /// Apply the range to a number, returns true if its value was changed
+    pub(crate) fn constrain(&self, reflect: &mut dyn Reflect) -> bool {
+        let range = match self.range {
+            Some(range) => range,
+            None => return false,
+        };
+        macro_rules! constrain_numbers {
+            ($($ty:ty),*) => {
+                $(
+                    if let Some(value) = reflect.downcast_mut::<$ty>() {
+                        let original = *value as f64;
+                        let constrained = range.apply(original);
+                        *value = constrained as $ty;
+                        return (constrained - original).abs() > f64::EPSILON;
+                    }
+                )*
+            };
+        }
+        constrain_numbers!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+        false
+    }
+}
+
+pub trait AppFieldAttributes {
+    /// Set the display options of the fields of `T`, registering `T` if needed
+    fn register_field_attributes<T: GetTypeRegistration>(
+        &mut self,
+        attributes: FieldAttributes,
+    ) -> &mut Self;
+}
+
+impl AppFieldAttributes for AppBuilder {
+    fn register_field_attributes<T: GetTypeRegistration>(
+        &mut self,
+        attributes: FieldAttributes,
+    ) -> &mut Self {
+        {
+            let type_registry = self.resources().get::<TypeRegistry>().unwrap();
+            let mut type_registry = type_registry.write();
+            if type_registry.get(TypeId::of::<T>()).is_none() {
+                type_registry.register::<T>();
+            }
+            type_registry
+                .get_mut(TypeId::of::<T>())
+                .unwrap()
+                .insert(attributes);
+        }
+        self
+    }
+}
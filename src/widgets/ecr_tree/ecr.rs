@@ -4,25 +4,48 @@ use std::ops::Deref;
 use bevy::reflect::ReflectResource;
 use bevy::{
     core::{Labels, Name},
-    ecs::{ComponentFlags, TypeInfo, With},
+    ecs::{Archetype, ComponentFlags, Location, TypeInfo, With},
+    math::{Rect, Size},
     prelude::{
-        trace, Added, Commands, DespawnRecursiveExt, Entity, Mutated, Or, Query, ReflectComponent,
-        Resources, World,
+        trace, warn, Added, AlignSelf, BuildChildren, Commands, DespawnRecursiveExt, Entity,
+        FlexDirection, Mutated, NodeBundle, Or, Query, Reflect, ReflectComponent, Res, Resources,
+        TextBundle, World,
     },
     reflect::TypeRegistry,
-    text::Text,
+    text::{Text, TextStyle},
+    ui::{self, Val},
+    utils::HashSet,
 };
-#[cfg(feature = "extra")]
-use std::any::TypeId;
+
+use crate::{
+    widgets::{
+        overhead::{Overhead, OverheadPart},
+        TextRole,
+    },
+    DebugIgnore,
+};
+use std::{any::TypeId, sync::Arc, time::Instant};
 
 pub fn update_system(world: &mut World, resources: &mut Resources) {
+    let start = Instant::now();
     let mut commands = Commands::default();
     commands.set_entity_reserver(world.get_entity_reserver());
+    // Visible root nodes of the scrolled trees, ordered once their widgets are spawned
+    let mut windows = Vec::new();
+    // Cloned once for all the trees, the visits only clone it again to display expanded values
+    let type_registry_arc = resources.get::<TypeRegistry>().unwrap().deref().clone();
+    let refresh = resources
+        .get::<super::LiveUpdates>()
+        .map_or(true, |live_updates| live_updates.is_refreshing());
 
     let entities = world
         .query_filtered::<Entity, With<super::State>>()
         .collect::<Vec<_>>();
     for container in entities {
+        // Between refreshes, only the trees whose widgets were used are updated
+        if !refresh && !world.get::<super::State>(container).unwrap().needs_update() {
+            continue;
+        }
         // Take ownership of the state so that world is not borrowed during the update
         let mut state = std::mem::take(&mut *world.get_mut::<super::State>(container).unwrap());
 
@@ -30,71 +53,163 @@ pub fn update_system(world: &mut World, resources: &mut Resources) {
         for (_, is_alive) in state.entries_alive.iter_mut() {
             *is_alive = false;
         }
+        state.begin_update(refresh);
 
         commands.set_current_entity(container);
 
-        // Visit all root keys
+        // Visit the root keys inside the viewport
         let keys = std::mem::take(&mut state.root_keys);
-        for &key in keys.iter() {
-            match key {
+        state
+            .window
+            .measure(world, container, &keys, &state.entries);
+        let range = state.window.update_range(&keys);
+        if state.window.viewport.is_some() {
+            recycle_entity_rows(&mut state, world, resources, &keys[range.clone()]);
+        }
+        for key in keys[range.clone()].iter() {
+            // Between refreshes, the values are only read again under the widgets that were used
+            if state.keep_without_refresh(key) {
+                continue;
+            }
+            match *key {
                 #[cfg(feature = "extra")]
                 super::Key::Resource { type_id } => {
-                    let type_registry_arc =
-                        resources.get::<TypeRegistry>().unwrap().deref().clone();
                     visit_resource(
                         &mut commands,
                         &mut state,
                         resources,
-                        type_registry_arc,
+                        &type_registry_arc,
                         type_id,
                         container,
                     );
                 }
                 super::Key::Entity { entity } => {
-                    let type_registry_arc =
-                        resources.get::<TypeRegistry>().unwrap().deref().clone();
                     visit_entity(
                         &mut commands,
                         &mut state,
                         world,
-                        type_registry_arc,
+                        &type_registry_arc,
                         entity,
                         container,
                     );
                 }
                 super::Key::Component { entity, type_id } => {
-                    let entity_location = world.get_entity_location(entity).unwrap();
-                    let type_info = *world.archetypes[entity_location.archetype as usize]
+                    // The entity or the component may have been removed since the root was added
+                    let entity_location = match world.get_entity_location(entity) {
+                        Some(entity_location) => entity_location,
+                        None => continue,
+                    };
+                    let type_info = match world.archetypes[entity_location.archetype as usize]
                         .types()
                         .iter()
                         .find(|type_info| type_info.id() == type_id)
-                        .unwrap();
-                    let type_registry_arc =
-                        resources.get::<TypeRegistry>().unwrap().deref().clone();
+                    {
+                        Some(type_info) => *type_info,
+                        None => continue,
+                    };
                     visit_component(
                         &mut commands,
                         &mut state,
                         world,
-                        type_registry_arc,
+                        &type_registry_arc,
                         entity,
+                        entity_location,
                         type_info,
+                        false,
+                        container,
+                    );
+                }
+                super::Key::ComponentType { type_id } => {
+                    visit_component_type(
+                        &mut commands,
+                        &mut state,
+                        world,
+                        &type_registry_arc,
+                        type_id,
+                        container,
+                    );
+                }
+                super::Key::SharedComponent {
+                    ref entities,
+                    type_id,
+                } => {
+                    visit_shared_component(
+                        &mut commands,
+                        &mut state,
+                        world,
+                        &type_registry_arc,
+                        entities,
+                        type_id,
                         container,
                     );
                 }
-                super::Key::ReflectNode { .. } => {
-                    unimplemented!();
+                super::Key::Archetype { index } => {
+                    visit_archetype(
+                        &mut commands,
+                        &mut state,
+                        world,
+                        &type_registry_arc,
+                        index,
+                        container,
+                    );
                 }
-                super::Key::ReflectLeaf { .. } => {
-                    unimplemented!();
+                super::Key::ReflectNode { .. } | super::Key::ReflectLeaf { .. } => {
+                    visit_reflect_root(
+                        &mut commands,
+                        &mut state,
+                        world,
+                        resources,
+                        &type_registry_arc,
+                        key,
+                        container,
+                    );
+                }
+                super::Key::Inspected => {
+                    super::inspector::visit_inspected(
+                        &mut commands,
+                        &mut state,
+                        world,
+                        resources,
+                        &type_registry_arc,
+                        container,
+                    );
+                }
+                super::Key::MapEntry { .. } | super::Key::MapInsert { .. } => {
+                    warn!("Map controls cannot be used as root keys");
+                }
+                super::Key::ComponentInfo { .. } => {
+                    warn!("Component informations cannot be used as root keys");
                 }
             }
         }
+        // The rows that were not bound to another entity are not needed anymore
+        for (widget, _) in state.window.free_rows.drain(..) {
+            commands.despawn_recursive(widget);
+        }
+        state.window.update_spacers(&mut commands, world, container);
+        if state.window.viewport.is_some() {
+            let rows = keys[range]
+                .iter()
+                .filter_map(|key| state.entries.get(key).map(|entry| entry.widget))
+                .collect::<Vec<_>>();
+            windows.push((container, rows));
+        }
+        state.visited_root_keys = keys.len();
+        state.window.moved = false;
         state.root_keys = keys;
 
-        // Delete all entries that were not visited
+        // Delete all entries that were not visited, except the content of the skipped components
         let entries_alive = &mut state.entries_alive;
         let entries = &mut state.entries;
+        let skipped_roots = &state.skipped_roots;
         entries_alive.retain(|key, is_alive| {
+            if !*is_alive
+                && key
+                    .path_root()
+                    .map_or(false, |root| skipped_roots.contains(root))
+            {
+                *is_alive = true;
+            }
             if !*is_alive {
                 trace!("removing node: {:?}", key);
                 let entry = entries.remove(key);
@@ -104,6 +219,26 @@ pub fn update_system(world: &mut World, resources: &mut Resources) {
             }
             *is_alive
         });
+        state.touched_roots.clear();
+        if refresh {
+            state.late_changes.clear();
+        }
+        let entries = &state.entries;
+        state
+            .filled_containers
+            .retain(|key, _| entries.contains_key(key));
+
+        // Restart the highlight of values that are still changing
+        for entity in state.highlighted.drain(..) {
+            if let Ok(mut highlight) = world.get_mut::<super::node::Highlight>(entity) {
+                highlight.restart();
+            } else {
+                commands.insert_one(
+                    entity,
+                    super::node::Highlight::new(state.style.color_highlight),
+                );
+            }
+        }
 
         // Put back the state in the component
         *world.get_mut::<super::State>(container).unwrap() = state;
@@ -111,6 +246,97 @@ pub fn update_system(world: &mut World, resources: &mut Resources) {
 
     // Apply changes
     commands.apply(world, resources);
+
+    for (container, rows) in windows {
+        let state = std::mem::take(&mut *world.get_mut::<super::State>(container).unwrap());
+        state.window.reorder(world, container, &rows);
+        *world.get_mut::<super::State>(container).unwrap() = state;
+    }
+
+    if let Some(overhead) = resources.get::<Overhead>() {
+        overhead.add(OverheadPart::Tree, start.elapsed());
+    }
+}
+
+/// Bind the collapsed entity rows that left the viewport to the entities that entered it,
+/// so that scrolling does not despawn and spawn whole rows
+fn recycle_entity_rows(
+    state: &mut super::State,
+    world: &mut World,
+    resources: &Resources,
+    visible: &[super::Key],
+) {
+    let visible_keys = visible.iter().collect::<HashSet<_>>();
+    let hidden_rows = state
+        .entries
+        .iter()
+        .filter_map(|(key, entry)| match (key, &entry.inner) {
+            (
+                super::Key::Entity { .. },
+                super::EntryType::Node {
+                    label,
+                    container: None,
+                },
+            ) if !visible_keys.contains(key) => Some((key.clone(), entry.widget, *label)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (key, widget, label) in hidden_rows {
+        state.entries.remove(&key);
+        state.entries_alive.remove(&key);
+        state.window.free_rows.push((widget, label));
+    }
+
+    let expansion_state = resources.get::<super::ExpansionState>();
+    for key in visible.iter() {
+        if state.window.free_rows.is_empty() {
+            break;
+        }
+        let entity = match *key {
+            super::Key::Entity { entity } if !state.entries.contains_key(key) => entity,
+            _ => continue,
+        };
+        if world.get_entity_location(entity).is_none() {
+            continue;
+        }
+        let persistent_id = entity_persistent_id(world, entity);
+        // The expanded rows are spawned again, the expansion is restored when their widget is added
+        if expansion_state.as_ref().map_or(false, |expansion_state| {
+            expansion_state.is_expanded(&persistent_id)
+        }) {
+            continue;
+        }
+        let (widget, label) = state.window.free_rows.pop().unwrap();
+        trace!("Recycling row {:?} for {:?}", widget, entity);
+        if let Ok(mut access) = world.get_mut::<super::EntryAccess>(widget) {
+            access.key = key.clone();
+        }
+        if let Ok(mut id) = world.get_mut::<super::PersistentId>(widget) {
+            id.0 = persistent_id;
+        }
+        let text = entity_label_text(
+            entity,
+            world.get::<Name>(entity).ok().as_deref(),
+            world.get::<Labels>(entity).ok().as_deref(),
+        );
+        if let Ok(mut entity_label) = world.get_mut::<EntityLabel>(label) {
+            entity_label.target = entity;
+        }
+        if let Ok(mut label_text) = world.get_mut::<Text>(label) {
+            label_text.sections[0].value = text;
+        }
+        super::node::rebind_node_buttons(world, label, key);
+        state.entries.insert(
+            key.clone(),
+            super::Entry {
+                widget,
+                inner: super::EntryType::Node {
+                    label,
+                    container: None,
+                },
+            },
+        );
+    }
 }
 
 #[cfg(feature = "extra")]
@@ -118,7 +344,7 @@ fn visit_resource(
     commands: &mut Commands,
     state: &mut super::State,
     resources: &mut Resources,
-    type_registry_arc: TypeRegistry,
+    type_registry_arc: &TypeRegistry,
     type_id: TypeId,
     container: Entity,
 ) {
@@ -128,56 +354,61 @@ fn visit_resource(
             entry
         } else {
             let type_registry = type_registry_arc.read();
-            let label = if let Some(registration) = type_registry.get(type_id) {
-                if registration.data::<ReflectResource>().is_some() {
+            let (label, persistent_id) = if let Some(registration) = type_registry.get(type_id) {
+                let label = if registration.data::<ReflectResource>().is_some() {
                     registration.short_name().to_string()
                 } else {
                     format!("{} (not a ReflectResource)", registration.short_name())
-                }
+                };
+                (label, registration.name().to_string())
             } else {
-                "(not a registered type)".to_string()
+                (
+                    "(not a registered type)".to_string(),
+                    format!("{:?}", type_id),
+                )
             };
-            let node = super::node::spawn_widget_node(key, commands, state, label, container);
+            drop(type_registry);
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                label,
+                persistent_id,
+                container,
+            );
             state.entries.insert(
-                key,
+                key.clone(),
                 super::Entry {
                     widget: node.root,
-                    inner: super::EntryType::Node { container: None },
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
                 },
             );
             state.entries.get_mut(&key).unwrap()
         }
     };
-    state.entries_alive.insert(key, true);
+    state.entries_alive.insert(key.clone(), true);
 
-    if let super::EntryType::Node { container, .. } = entry.inner {
+    if let super::EntryType::Node { label, container } = entry.inner {
         if let Some(container) = container {
-            let type_registry = type_registry_arc.read();
-            if let Some(registration) = type_registry.get(type_id) {
-                if let Some(reflect_resource) = registration.data::<ReflectResource>().cloned() {
-                    drop(type_registry);
-                    reflect_resource.borrow_mut_resource(resources);
-                    // Safety:
-                    //      we just borrowed the reflected resource exclusively
-                    //      resources is not used to access the reflected resource until it is released
-                    let resource = unsafe { reflect_resource.reflect_resource_mut(resources) };
-                    let mutated = super::node::dispatch_reflect(
-                        commands,
-                        state,
-                        type_registry_arc,
-                        resource,
-                        container,
-                    );
-                    if mutated {
-                        trace!("Resource mutated: {}", resource.type_name());
-                        resources.set_mutated_dynamic(&type_id);
-                    }
-                    // Safety: the reflected resource was borrowed in this function
-                    unsafe {
-                        reflect_resource.release_mut_resource(resources);
-                    }
-                }
-            }
+            let changes_seen = state.external_changes;
+            with_reflected_resource(resources, type_registry_arc, type_id, |resource, info| {
+                state.path_root = Some(Arc::new(key.clone()));
+                state.path_root_name = info.name;
+                state.path_root_id = info.persistent_id;
+                let mutated = super::node::dispatch_reflect(
+                    commands,
+                    state,
+                    type_registry_arc.clone(),
+                    resource,
+                    container,
+                );
+                state.path_root = None;
+                mutated
+            });
+            state.highlight_if_changed(label, changes_seen);
         }
     } else {
         unreachable!();
@@ -188,32 +419,46 @@ fn visit_entity(
     commands: &mut Commands,
     state: &mut super::State,
     world: &mut World,
-    type_registry_arc: TypeRegistry,
+    type_registry_arc: &TypeRegistry,
     entity: Entity,
     container: Entity,
 ) {
+    // The keys of the Entities panel are only collected when the archetypes change
+    if world.get_entity_location(entity).is_none() {
+        return;
+    }
     let key = super::Key::Entity { entity };
     let entry = {
         if let Some(entry) = state.entries.get_mut(&key) {
             entry
         } else {
-            let node =
-                super::node::spawn_widget_node(key, commands, state, String::new(), container);
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                String::new(),
+                entity_persistent_id(world, entity),
+                container,
+            );
             commands.insert_one(node.label, EntityLabel { target: entity });
             state.entries.insert(
-                key,
+                key.clone(),
                 super::Entry {
                     widget: node.root,
-                    inner: super::EntryType::Node { container: None },
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
                 },
             );
             state.entries.get_mut(&key).unwrap()
         }
     };
-    state.entries_alive.insert(key, true);
+    state.entries_alive.insert(key.clone(), true);
 
-    if let super::EntryType::Node { container, .. } = entry.inner {
+    if let super::EntryType::Node { label, container } = entry.inner {
         if let Some(container) = container {
+            let changes_seen = state.external_changes;
             let entity_location = world.get_entity_location(entity).unwrap();
             let component_types =
                 Vec::from(world.archetypes[entity_location.archetype as usize].types());
@@ -222,25 +467,625 @@ fn visit_entity(
                     commands,
                     state,
                     world,
-                    type_registry_arc.clone(),
+                    type_registry_arc,
                     entity,
+                    entity_location,
                     type_info,
+                    false,
                     container,
                 );
             }
+            state.highlight_if_changed(label, changes_seen);
+        } else if state.refresh {
+            // The components are not visited, their flags tell if they were modified
+            let entity_location = world.get_entity_location(entity).unwrap();
+            let changed = world.archetypes[entity_location.archetype as usize]
+                .types()
+                .iter()
+                .any(|type_info| {
+                    let type_id = type_info.id();
+                    is_component_changed(&world.archetypes, entity_location, type_id)
+                        && !state.is_touched(&super::Key::Component { entity, type_id })
+                });
+            state.highlight_collapsed(label, changed);
+        }
+    } else {
+        unreachable!();
+    }
+}
+
+fn visit_archetype(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &mut World,
+    type_registry_arc: &TypeRegistry,
+    index: u32,
+    container: Entity,
+) {
+    if index as usize >= world.archetypes.len() {
+        warn!("Archetype #{} does not exist", index);
+        return;
+    }
+    let key = super::Key::Archetype { index };
+    let entry = {
+        if let Some(entry) = state.entries.get_mut(&key) {
+            entry
+        } else {
+            // Archetype indices change between runs, but not their components
+            let mut type_names = world.archetypes[index as usize]
+                .types()
+                .iter()
+                .map(|type_info| type_info.type_name())
+                .collect::<Vec<_>>();
+            type_names.sort_unstable();
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                String::new(),
+                format!("archetype/{}", type_names.join(",")),
+                container,
+            );
+            commands.insert_one(
+                node.root,
+                SummaryLabel {
+                    label: node.label,
+                    entity_count: None,
+                },
+            );
+            state.entries.insert(
+                key.clone(),
+                super::Entry {
+                    widget: node.root,
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
+                },
+            );
+            state.entries.get_mut(&key).unwrap()
+        }
+    };
+    state.entries_alive.insert(key.clone(), true);
+
+    let entity_count = world.archetypes[index as usize].len() as usize;
+    update_summary_label(world, entry.widget, entity_count, state.refresh, |world| {
+        archetype_summary(world, type_registry_arc, index)
+    });
+
+    if let super::EntryType::Node { label, container } = entry.inner {
+        if let Some(container) = container {
+            let changes_seen = state.external_changes;
+            let entities = world.archetypes[index as usize]
+                .iter_entities()
+                .copied()
+                .collect::<Vec<_>>();
+            for entity in entities {
+                visit_entity(commands, state, world, type_registry_arc, entity, container);
+            }
+            state.highlight_if_changed(label, changes_seen);
         }
     } else {
         unreachable!();
     }
 }
 
+/// Components, entity count and memory used by an archetype
+fn archetype_summary(world: &World, type_registry_arc: &TypeRegistry, index: u32) -> String {
+    let type_registry = type_registry_arc.read();
+    let archetype = &world.archetypes[index as usize];
+    let type_names = archetype
+        .types()
+        .iter()
+        .map(|type_info| {
+            type_registry
+                .get(type_info.id())
+                .map(|registration| registration.short_name().to_string())
+                .unwrap_or_else(|| type_info.type_name().to_string())
+        })
+        .collect::<Vec<_>>();
+    let entity_size: usize = archetype
+        .types()
+        .iter()
+        .map(|type_info| type_info.layout().size())
+        .sum();
+    format!(
+        "#{} [{}] {} entities, {} bytes",
+        index,
+        type_names.join(", "),
+        archetype.len(),
+        entity_size * archetype.len() as usize
+    )
+}
+
+/// Visit the component of the first entity, and write the edits to the others
+fn visit_shared_component(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &mut World,
+    type_registry_arc: &TypeRegistry,
+    entities: &[Entity],
+    type_id: TypeId,
+    container: Entity,
+) {
+    let locations = entities
+        .iter()
+        .map(|entity| {
+            world.get_entity_location(*entity).filter(|location| {
+                world.archetypes[location.archetype as usize].has_dynamic(type_id)
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    let locations = match locations {
+        Some(locations) if !locations.is_empty() => locations,
+        _ => {
+            trace!("Selected entities do not share the component {:?}", type_id);
+            return;
+        }
+    };
+    let type_registry = type_registry_arc.read();
+    let (reflect_component, short_name, root_id) =
+        match type_registry.get(type_id).and_then(|registration| {
+            registration
+                .data::<ReflectComponent>()
+                .map(|reflect_component| (reflect_component.clone(), registration))
+        }) {
+            Some((reflect_component, registration)) => (
+                reflect_component,
+                registration.short_name().to_string(),
+                format!("selection/{}", registration.name()),
+            ),
+            None => {
+                warn!("Shared components must be a ReflectComponent");
+                return;
+            }
+        };
+    drop(type_registry);
+
+    let key = super::Key::SharedComponent {
+        entities: entities.to_vec(),
+        type_id,
+    };
+    let entry = {
+        if let Some(entry) = state.entries.get_mut(&key) {
+            entry
+        } else {
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                format!("{} ({} entities)", short_name, entities.len()),
+                root_id.clone(),
+                container,
+            );
+            state.entries.insert(
+                key.clone(),
+                super::Entry {
+                    widget: node.root,
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
+                },
+            );
+            state.entries.get_mut(&key).unwrap()
+        }
+    };
+    state.entries_alive.insert(key.clone(), true);
+
+    if let super::EntryType::Node { label, container } = entry.inner {
+        if let Some(container) = container {
+            let changes_seen = state.external_changes;
+            // Copy the values of the other entities to show the fields that differ
+            state.merged_with = locations[1..]
+                .iter()
+                .map(|location| {
+                    let archetype = &mut world.archetypes[location.archetype as usize];
+                    // SAFETY: the reflected component is cloned before world is used again
+                    let component = unsafe {
+                        reflect_component.reflect_component_mut(archetype, location.index)
+                    };
+                    component.clone_value()
+                })
+                .collect();
+
+            let location = locations[0];
+            let entity_archetype = &mut world.archetypes[location.archetype as usize];
+            // SAFETY:
+            //      we just obtained entity_archetype and location from world so they are valid
+            //      world and entity_archetype are not used to access the reflected component until it is dropped
+            let component = unsafe {
+                reflect_component.reflect_component_mut(entity_archetype, location.index)
+            };
+            state.path_root = Some(Arc::new(key));
+            state.path_root_name = short_name;
+            state.path_root_id = root_id;
+            let mutated = super::node::dispatch_reflect(
+                commands,
+                state,
+                type_registry_arc.clone(),
+                component,
+                container,
+            );
+            state.path_root = None;
+            state.merged_with.clear();
+            if mutated {
+                set_component_mutated(entity_archetype, type_id);
+            }
+
+            let edits = std::mem::take(&mut state.merged_edits);
+            if !edits.is_empty() {
+                for location in locations[1..].iter() {
+                    let entity_archetype = &mut world.archetypes[location.archetype as usize];
+                    // SAFETY: same as above
+                    let component = unsafe {
+                        reflect_component.reflect_component_mut(entity_archetype, location.index)
+                    };
+                    for (path, value) in edits.iter() {
+                        if let Some(field) = path.resolve(&mut *component) {
+                            if field.set(value.clone_value()).is_err() {
+                                warn!("Could not write {} to all the selected entities", path);
+                            }
+                        }
+                    }
+                    set_component_mutated(entity_archetype, type_id);
+                }
+            }
+            state.highlight_if_changed(label, changes_seen);
+        }
+    } else {
+        unreachable!();
+    }
+}
+
+/// Remember the expanded components that were modified after the tree update, by the systems running after it.
+/// Their change flags are cleared at the end of the frame, before the tree can see them.
+pub fn late_changes_system(world: &mut World, resources: &mut Resources) {
+    let _measure = resources
+        .get::<Overhead>()
+        .map(|overhead| overhead.measure(OverheadPart::Tree));
+    let containers = world
+        .query_filtered::<Entity, With<super::State>>()
+        .collect::<Vec<_>>();
+    for container in containers {
+        let late_changes = {
+            let state = world.get::<super::State>(container).unwrap();
+            state
+                .filled_containers
+                .keys()
+                .filter(|key| !state.changed_when_visited.contains(key))
+                .filter(|key| match key {
+                    super::Key::Component { entity, type_id } => world
+                        .get_entity_location(*entity)
+                        .map_or(false, |location| {
+                            is_component_changed(&world.archetypes, location, *type_id)
+                        }),
+                    _ => false,
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let mut state = world.get_mut::<super::State>(container).unwrap();
+        state.changed_when_visited.clear();
+        state.late_changes.extend(late_changes);
+    }
+}
+
+/// Whether a component of an entity was added or mutated since the trackers were last cleared
+fn is_component_changed(archetypes: &[Archetype], location: Location, type_id: TypeId) -> bool {
+    archetypes[location.archetype as usize]
+        .get_type_state(type_id)
+        .map_or(true, |component_state| {
+            // Safety: the flags are read for an entity of this archetype
+            let flags = unsafe {
+                *component_state
+                    .component_flags()
+                    .as_ptr()
+                    .add(location.index)
+            };
+            !flags.is_empty()
+        })
+}
+
+fn set_component_mutated(entity_archetype: &mut Archetype, type_id: TypeId) {
+    let component_state = entity_archetype.get_type_state_mut(type_id).unwrap();
+    // Safety: world is borrowed exclusively
+    unsafe {
+        component_state
+            .component_flags()
+            .as_mut()
+            .insert(ComponentFlags::MUTATED);
+    }
+}
+
+/// Name and persistent id of the component or resource that owns reflected values
+pub(crate) struct RootInfo {
+    pub name: String,
+    pub persistent_id: String,
+}
+
+/// Borrow the reflected component or resource designated by a root key, it is marked as mutated if `visit` returns true.
+/// Returns None if it does not exist or cannot be reflected.
+pub(crate) fn with_reflected_root(
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    root: &super::Key,
+    visit: impl FnOnce(&mut dyn Reflect, RootInfo) -> bool,
+) -> Option<bool> {
+    match *root {
+        #[cfg(feature = "extra")]
+        super::Key::Resource { type_id } => {
+            with_reflected_resource(resources, type_registry_arc, type_id, visit)
+        }
+        super::Key::Component { entity, type_id } => {
+            let location = world.get_entity_location(entity)?;
+            with_reflected_component(world, type_registry_arc, entity, location, type_id, visit)
+        }
+        _ => {
+            let _ = resources;
+            None
+        }
+    }
+}
+
+#[cfg(feature = "extra")]
+fn with_reflected_resource(
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    type_id: TypeId,
+    visit: impl FnOnce(&mut dyn Reflect, RootInfo) -> bool,
+) -> Option<bool> {
+    let type_registry = type_registry_arc.read();
+    let registration = type_registry.get(type_id)?;
+    let reflect_resource = registration.data::<ReflectResource>()?.clone();
+    let info = RootInfo {
+        name: registration.short_name().to_string(),
+        persistent_id: registration.name().to_string(),
+    };
+    drop(type_registry);
+    reflect_resource.borrow_mut_resource(resources);
+    // Safety:
+    //      we just borrowed the reflected resource exclusively
+    //      resources is not used to access the reflected resource until it is released
+    let resource = unsafe { reflect_resource.reflect_resource_mut(resources) };
+    let mutated = visit(resource, info);
+    if mutated {
+        trace!("Resource mutated: {}", resource.type_name());
+        resources.set_mutated_dynamic(&type_id);
+    }
+    // Safety: the reflected resource was borrowed in this function
+    unsafe {
+        reflect_resource.release_mut_resource(resources);
+    }
+    Some(mutated)
+}
+
+fn with_reflected_component(
+    world: &mut World,
+    type_registry_arc: &TypeRegistry,
+    entity: Entity,
+    location: Location,
+    type_id: TypeId,
+    visit: impl FnOnce(&mut dyn Reflect, RootInfo) -> bool,
+) -> Option<bool> {
+    let type_info = *world.archetypes[location.archetype as usize]
+        .types()
+        .iter()
+        .find(|type_info| type_info.id() == type_id)?;
+    let type_registry = type_registry_arc.read();
+    let registration = type_registry.get(type_id)?;
+    let reflect_component = registration.data::<ReflectComponent>()?.clone();
+    let info = RootInfo {
+        name: format!("{:?} {}", entity, registration.short_name()),
+        persistent_id: component_persistent_id(world, entity, &type_info),
+    };
+    drop(type_registry);
+    let entity_archetype = &mut world.archetypes[location.archetype as usize];
+    // SAFETY:
+    //      location was obtained from world so it is valid
+    //      world and entity_archetype are not used to access the reflected component until it is dropped
+    let component =
+        unsafe { reflect_component.reflect_component_mut(entity_archetype, location.index) };
+    let mutated = visit(component, info);
+    if mutated {
+        trace!("Component mutated: {:?}::{}", entity, component.type_name());
+        set_component_mutated(entity_archetype, type_id);
+    }
+    Some(mutated)
+}
+
+/// Write a value at the path of a reflected key, directly into the components or resource that own it
+pub(crate) fn set_reflected_value(
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    key: &super::Key,
+    value: &dyn Reflect,
+) -> Result<(), String> {
+    let path = match key {
+        super::Key::ReflectNode { path, .. } | super::Key::ReflectLeaf { path, .. } => path,
+        _ => return Err("not a reflected value".to_string()),
+    };
+    let roots = match &*path.root {
+        super::Key::SharedComponent { entities, type_id } => entities
+            .iter()
+            .map(|entity| super::Key::Component {
+                entity: *entity,
+                type_id: *type_id,
+            })
+            .collect(),
+        root => vec![root.clone()],
+    };
+    for root in roots.iter() {
+        let mut result = Err(format!("{} does not exist anymore", path));
+        let found = with_reflected_root(world, resources, type_registry_arc, root, |reflect, _| {
+            if let Some(field) = path.resolve(reflect) {
+                result = field
+                    .set(value.clone_value())
+                    .map_err(|_| format!("{} has another type", path));
+            }
+            result.is_ok()
+        });
+        if found.is_none() {
+            return Err(format!("{:?} does not exist anymore", root));
+        }
+        result?;
+    }
+    Ok(())
+}
+
+/// Label of a node that is updated while the node is visited
+pub struct SummaryLabel {
+    label: Entity,
+    /// Number of entities in the summary, it is only written again when the number changes
+    entity_count: Option<usize>,
+}
+
+/// The summary is only written once between refreshes, when the label is spawned
+fn update_summary_label(
+    world: &mut World,
+    widget: Entity,
+    entity_count: usize,
+    refresh: bool,
+    summary: impl FnOnce(&World) -> String,
+) {
+    let label = match world.get_mut::<SummaryLabel>(widget) {
+        Ok(mut node)
+            if node.entity_count != Some(entity_count)
+                && (refresh || node.entity_count.is_none()) =>
+        {
+            node.entity_count = Some(entity_count);
+            node.label
+        }
+        _ => return,
+    };
+    let summary = summary(world);
+    if let Ok(mut text) = world.get_mut::<Text>(label) {
+        text.sections[0].value = summary;
+    }
+}
+
+fn visit_component_type(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &mut World,
+    type_registry_arc: &TypeRegistry,
+    type_id: TypeId,
+    container: Entity,
+) {
+    // Entities of the debug menu are not shown
+    let archetypes = world
+        .archetypes
+        .iter()
+        .enumerate()
+        .filter(|(_, archetype)| archetype.has_dynamic(type_id) && !archetype.has::<DebugIgnore>())
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+    let type_info = match archetypes.first().and_then(|index| {
+        world.archetypes[*index as usize]
+            .types()
+            .iter()
+            .find(|type_info| type_info.id() == type_id)
+            .copied()
+    }) {
+        Some(type_info) => type_info,
+        None => {
+            trace!("No entity has the component {:?}", type_id);
+            return;
+        }
+    };
+    let key = super::Key::ComponentType { type_id };
+    let entry = {
+        if let Some(entry) = state.entries.get_mut(&key) {
+            entry
+        } else {
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                String::new(),
+                format!("component/{}", type_info.type_name()),
+                container,
+            );
+            commands.insert_one(
+                node.root,
+                SummaryLabel {
+                    label: node.label,
+                    entity_count: None,
+                },
+            );
+            state.entries.insert(
+                key.clone(),
+                super::Entry {
+                    widget: node.root,
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
+                },
+            );
+            state.entries.get_mut(&key).unwrap()
+        }
+    };
+    state.entries_alive.insert(key.clone(), true);
+
+    let count: usize = archetypes
+        .iter()
+        .map(|index| world.archetypes[*index as usize].len() as usize)
+        .sum();
+    update_summary_label(world, entry.widget, count, state.refresh, |_| {
+        let type_name = type_registry_arc
+            .read()
+            .get(type_id)
+            .map(|registration| registration.short_name().to_string())
+            .unwrap_or_else(|| type_info.type_name().to_string());
+        format!("{} ({} entities)", type_name, count)
+    });
+
+    if let super::EntryType::Node { label, container } = entry.inner {
+        if let Some(container) = container {
+            let changes_seen = state.external_changes;
+            let entities = archetypes
+                .iter()
+                .flat_map(|&archetype| {
+                    world.archetypes[archetype as usize]
+                        .iter_entities()
+                        .enumerate()
+                        .map(move |(index, entity)| (*entity, Location { archetype, index }))
+                })
+                .collect::<Vec<_>>();
+            for (entity, location) in entities {
+                visit_component(
+                    commands,
+                    state,
+                    world,
+                    type_registry_arc,
+                    entity,
+                    location,
+                    type_info,
+                    true,
+                    container,
+                );
+            }
+            state.highlight_if_changed(label, changes_seen);
+        }
+    } else {
+        unreachable!();
+    }
+}
+
+/// When `label_entity` is true the node is labeled with the entity instead of the component type
+#[allow(clippy::clippy::too_many_arguments)]
 fn visit_component(
     commands: &mut Commands,
     state: &mut super::State,
     world: &mut World,
-    type_registry_arc: TypeRegistry,
+    type_registry_arc: &TypeRegistry,
     entity: Entity,
+    entity_location: Location,
     type_info: TypeInfo,
+    label_entity: bool,
     container: Entity,
 ) {
     let type_id = type_info.id();
@@ -254,30 +1099,53 @@ fn visit_component(
                 if registration.data::<ReflectComponent>().is_some() {
                     registration.short_name().to_string()
                 } else {
-                    format!("{} (not a ReflectComponent", registration.short_name())
+                    format!("{} (not a ReflectComponent)", registration.short_name())
                 }
             } else {
                 format!("{} (not a registered type)", type_info.type_name())
             };
-            let node = super::node::spawn_widget_node(key, commands, state, label, container);
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                if label_entity { String::new() } else { label },
+                component_persistent_id(world, entity, &type_info),
+                container,
+            );
+            if label_entity {
+                commands.insert_one(node.label, EntityLabel { target: entity });
+            }
             state.entries.insert(
-                key,
+                key.clone(),
                 super::Entry {
                     widget: node.root,
-                    inner: super::EntryType::Node { container: None },
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
                 },
             );
             state.entries.get_mut(&key).unwrap()
         }
     };
-    state.entries_alive.insert(key, true);
+    state.entries_alive.insert(key.clone(), true);
 
-    if let super::EntryType::Node { container, .. } = entry.inner {
+    if let super::EntryType::Node { label, container } = entry.inner {
         if let Some(container) = container {
+            let changes_seen = state.external_changes;
             let type_registry = type_registry_arc.read();
             if let Some(registration) = type_registry.get(type_id) {
                 if let Some(reflect_component) = registration.data::<ReflectComponent>() {
-                    let entity_location = world.get_entity_location(entity).unwrap();
+                    let changed = is_component_changed(&world.archetypes, entity_location, type_id);
+                    if state.can_skip(&key, container, changed) {
+                        state.skipped_roots.insert(key);
+                        return;
+                    }
+                    if changed {
+                        state.changed_when_visited.insert(key.clone());
+                    }
+                    state.filled_containers.insert(key.clone(), container);
+                    let root_id = component_persistent_id(world, entity, &type_info);
                     let entity_archetype =
                         &mut world.archetypes[entity_location.archetype as usize];
                     // SAFETY:
@@ -287,16 +1155,22 @@ fn visit_component(
                         reflect_component
                             .reflect_component_mut(entity_archetype, entity_location.index)
                     };
+                    state.path_root = Some(Arc::new(key.clone()));
+                    state.path_root_name = format!("{:?} {}", entity, registration.short_name());
+                    state.path_root_id = root_id;
                     drop(type_registry);
                     let mutated = super::node::dispatch_reflect(
                         commands,
                         state,
-                        type_registry_arc,
+                        type_registry_arc.clone(),
                         component,
                         container,
                     );
+                    state.path_root = None;
                     if mutated {
                         trace!("Component mutated: {:?}::{}", entity, component.type_name());
+                        // Written by the tree, not by a system running after it
+                        state.changed_when_visited.insert(key);
                         let component_state = entity_archetype.get_type_state_mut(type_id).unwrap();
                         // Safety: world is borrowed exclusively
                         unsafe {
@@ -306,14 +1180,308 @@ fn visit_component(
                                 .insert(ComponentFlags::MUTATED);
                         }
                     }
+                    state.highlight_if_changed(label, changes_seen);
+                    return;
                 }
             }
+            drop(type_registry);
+            visit_component_info(
+                commands,
+                state,
+                world,
+                type_registry_arc,
+                entity,
+                type_info,
+                container,
+            );
+        } else if state.refresh {
+            // The component is not visited, its flags tell if it was modified
+            let changed = is_component_changed(&world.archetypes, entity_location, type_id)
+                && !state.is_touched(&key);
+            state.highlight_collapsed(label, changed);
         }
     } else {
         unreachable!();
     }
 }
 
+/// Explain why a component cannot be displayed and how to fix it
+fn visit_component_info(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &World,
+    type_registry_arc: &TypeRegistry,
+    entity: Entity,
+    type_info: TypeInfo,
+    container: Entity,
+) {
+    let type_id = type_info.id();
+    let key = super::Key::ComponentInfo { entity, type_id };
+    if !state.entries.contains_key(&key) {
+        let hint = if let Some(registration) = type_registry_arc.read().get(type_id) {
+            format!(
+                "hint: add #[reflect(Component)] to the derive of {}",
+                registration.short_name()
+            )
+        } else {
+            format!(
+                "hint: derive Reflect with #[reflect(Component)] and call app.register_type::<{}>()",
+                type_info.type_name()
+            )
+        };
+        let entity_location = world.get_entity_location(entity).unwrap();
+        let archetype = &world.archetypes[entity_location.archetype as usize];
+        let lines = vec![
+            format!("size: {} bytes", type_info.layout().size()),
+            format!(
+                "archetype: #{} ({} components, {} entities)",
+                entity_location.archetype,
+                archetype.types().len(),
+                archetype.len()
+            ),
+            hint,
+        ];
+        let widget = spawn_info_text(commands, state, lines, container);
+        state.entries.insert(
+            key.clone(),
+            super::Entry {
+                widget,
+                inner: super::EntryType::Info,
+            },
+        );
+    }
+    state.entries_alive.insert(key, true);
+}
+
+fn spawn_info_text(
+    commands: &mut Commands,
+    state: &super::State,
+    lines: Vec<String>,
+    container: Entity,
+) -> Entity {
+    let mut widget = None;
+    commands.set_current_entity(container);
+    commands.with_children(|parent| {
+        widget = parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    flex_shrink: 0.,
+                    margin: Rect {
+                        left: Val::Px(16.0 + 2. * state.style.spacing),
+                        right: Val::Px(state.style.spacing),
+                        top: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: state.style.color_background.clone(),
+                ..Default::default()
+            })
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                for line in lines {
+                    parent
+                        .spawn(TextBundle {
+                            style: ui::Style {
+                                align_self: AlignSelf::FlexStart,
+                                size: Size {
+                                    width: Val::Undefined,
+                                    height: Val::Px(state.style.font_size_small()),
+                                },
+                                flex_shrink: 0.,
+                                margin: Rect::all(Val::Px(state.style.spacing / 2.)),
+                                ..Default::default()
+                            },
+                            text: Text::with_section(
+                                line,
+                                TextStyle {
+                                    font: state.style.font.clone(),
+                                    font_size: state.style.font_size_small(),
+                                    color: state.style.color_node_text,
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        })
+                        .with(TextRole::Text)
+                        .with(DebugIgnore);
+                }
+            })
+            .current_entity();
+    });
+    widget.unwrap()
+}
+
+/// Visit a value inside a component or resource, when it is used as a root key
+fn visit_reflect_root(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    key: &super::Key,
+    container: Entity,
+) {
+    let path = match key {
+        super::Key::ReflectNode { path, .. } | super::Key::ReflectLeaf { path, .. } => path,
+        _ => unreachable!(),
+    };
+    match *path.root {
+        super::Key::Inspected => {
+            let persistent_id = format!("inspector/{}", state.inspected_name);
+            super::inspector::visit_source(state, world, resources, &mut |state, reflect| {
+                state.path_root = Some(Arc::new(super::Key::Inspected));
+                state.path_root_name = state.inspected_name.clone();
+                state.path_root_id = persistent_id.clone();
+                visit_reflect_target(
+                    commands,
+                    state,
+                    type_registry_arc.clone(),
+                    reflect,
+                    key,
+                    container,
+                )
+            });
+        }
+        _ => {
+            let found = with_reflected_root(
+                world,
+                resources,
+                type_registry_arc,
+                &path.root,
+                |root, info| {
+                    state.path_root = Some(path.root.clone());
+                    state.path_root_name = info.name;
+                    state.path_root_id = info.persistent_id;
+                    visit_reflect_target(
+                        commands,
+                        state,
+                        type_registry_arc.clone(),
+                        root,
+                        key,
+                        container,
+                    )
+                },
+            );
+            if found.is_none() {
+                trace!("Root of reflected value not found: {:?}", path.root);
+            }
+        }
+    }
+}
+
+/// Find the value of a reflected root key inside its owner and visit it
+fn visit_reflect_target(
+    commands: &mut Commands,
+    state: &mut super::State,
+    type_registry_arc: TypeRegistry,
+    root: &mut dyn Reflect,
+    key: &super::Key,
+    container: Entity,
+) -> bool {
+    let (path, type_id, variant_index) = match key {
+        super::Key::ReflectNode {
+            path,
+            type_id,
+            variant_index,
+        }
+        | super::Key::ReflectLeaf {
+            path,
+            type_id,
+            variant_index,
+        } => (path, *type_id, *variant_index),
+        _ => unreachable!(),
+    };
+    // Follow the path from the root, with the attributes its fields would have in the whole tree
+    let mut attribute = super::attributes::FieldAttribute::default();
+    let mut target = Some(root);
+    for segment in path.segments.iter() {
+        let current = match target {
+            Some(current) => current,
+            None => break,
+        };
+        attribute = match segment {
+            super::PathSegment::Field(field) => super::attributes::FieldAttribute::of_field(
+                &type_registry_arc,
+                current.type_id(),
+                field,
+                &attribute,
+            ),
+            _ => super::attributes::FieldAttribute::of_element(&attribute),
+        };
+        target = segment.resolve(current);
+    }
+    let mut mutated = false;
+    match target {
+        Some(reflect) if reflect.type_id() == type_id => {
+            let name = format!("{}{}: ", state.path_root_name, path);
+            state.path = path.segments.clone();
+            let parent_attribute = std::mem::replace(&mut state.attribute, attribute);
+            if let super::Key::ReflectNode { .. } = key {
+                mutated = super::node::visit_reflect_node(
+                    commands,
+                    state,
+                    type_registry_arc,
+                    reflect,
+                    name,
+                    variant_index,
+                    container,
+                );
+            } else {
+                let spawner = state
+                    .specialized_widgets
+                    .get(&type_id)
+                    .copied()
+                    .unwrap_or(super::leaf::spawn_widget_default as super::FnSpawnWidget);
+                mutated = super::leaf::visit_reflect_leaf(
+                    &spawner,
+                    commands,
+                    state,
+                    type_registry_arc,
+                    reflect,
+                    name,
+                    variant_index,
+                    container,
+                );
+            }
+            state.path.clear();
+            state.attribute = parent_attribute;
+        }
+        _ => trace!("Reflected value not found: {:?}", key),
+    }
+    state.path_root = None;
+    mutated
+}
+
+/// Entities are identified by their name if they have one, so that their state survives restarts
+fn entity_persistent_id(world: &World, entity: Entity) -> String {
+    if let Ok(name) = world.get::<Name>(entity) {
+        // The entities that have the same name are told apart by their rank among them
+        let ordinal = world
+            .query::<(Entity, &Name)>()
+            .filter(|(other, other_name)| {
+                other.id() < entity.id() && other_name.as_str() == name.as_str()
+            })
+            .count();
+        if ordinal == 0 {
+            name.as_str().to_string()
+        } else {
+            format!("{}#{}", name.as_str(), ordinal)
+        }
+    } else {
+        format!("{:?}", entity)
+    }
+}
+
+fn component_persistent_id(world: &World, entity: Entity, type_info: &TypeInfo) -> String {
+    format!(
+        "{}/{}",
+        entity_persistent_id(world, entity),
+        type_info.type_name()
+    )
+}
+
 pub struct EntityLabel {
     target: Entity,
 }
@@ -323,26 +1491,32 @@ pub fn update_entity_labels(
     query_added: Query<(), Added<EntityLabel>>,
     query_mutated: Query<(), Or<(Mutated<Name>, Mutated<Labels>)>>,
     query_entity: Query<(Option<&Name>, Option<&Labels>)>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (entity, mut text, entity_label) in query_label.iter_mut() {
         if query_added.get(entity).is_ok() || query_mutated.get(entity_label.target).is_ok() {
             let (name, labels) = query_entity.get(entity_label.target).unwrap();
-            let value = &mut text.sections[0].value;
-            *value = format!("{:?}", entity_label.target);
-            if let Some(name) = name {
-                value.push(' ');
-                value.push_str(name.as_str())
-            }
-            if let Some(labels) = labels {
-                value.push_str(" [");
-                for (i, entity_label) in labels.iter().enumerate() {
-                    if i > 0 {
-                        value.push_str(", ");
-                    }
-                    value.push_str(entity_label);
-                }
-                value.push(']');
+            text.sections[0].value = entity_label_text(entity_label.target, name, labels);
+        }
+    }
+}
+
+fn entity_label_text(entity: Entity, name: Option<&Name>, labels: Option<&Labels>) -> String {
+    let mut value = format!("{:?}", entity);
+    if let Some(name) = name {
+        value.push(' ');
+        value.push_str(name.as_str())
+    }
+    if let Some(labels) = labels {
+        value.push_str(" [");
+        for (i, label) in labels.iter().enumerate() {
+            if i > 0 {
+                value.push_str(", ");
             }
+            value.push_str(label);
         }
+        value.push(']');
     }
+    value
 }
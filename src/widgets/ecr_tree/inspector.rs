@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::{
+        BuildChildren, Children, Commands, Entity, FlexDirection, NodeBundle, Reflect, Resources,
+        World,
+    },
+    reflect::TypeRegistry,
+    ui::{self, Size, Val},
+};
+
+use crate::DebugIgnore;
+
+/// Closure that gives access to an inspected value by calling the visitor with it.
+/// The visitor returns true if the value was modified through the inspector, and the closure should return the same.
+pub type FnInspect = dyn FnMut(&mut World, &mut Resources, &mut dyn FnMut(&mut dyn Reflect) -> bool) -> bool
+    + Send
+    + Sync;
+
+/// Value displayed by an inspector that is not an entity, a component or a resource
+pub enum InspectorSource {
+    /// Value owned by the inspector, available with [`super::State::inspected_value`]
+    Owned(Box<dyn Reflect>),
+    /// Value provided by a closure on every update
+    Closure(Box<FnInspect>),
+}
+
+pub trait BuildInspector {
+    /// Spawn a tree inspecting `source` as a child of the current entity.
+    /// The returned entity holds the [`super::State`] of the tree.
+    fn spawn_inspector(
+        &mut self,
+        name: String,
+        source: InspectorSource,
+        style: super::Style,
+    ) -> Entity;
+}
+
+impl BuildInspector for Commands {
+    fn spawn_inspector(
+        &mut self,
+        name: String,
+        source: InspectorSource,
+        style: super::Style,
+    ) -> Entity {
+        let mut entity = None;
+        self.with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: ui::Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        size: Size {
+                            width: Val::Percent(100.),
+                            height: Val::Undefined, // Height will grow as needed
+                        },
+                        flex_shrink: 0.,
+                        ..Default::default()
+                    },
+                    material: style.color_background.clone(),
+                    ..Default::default()
+                })
+                .with(Children::default())
+                .with(DebugIgnore);
+            entity = parent.current_entity();
+            let mut state = super::State::new(entity.unwrap(), style);
+            state.inspected = Some(source);
+            state.inspected_name = name;
+            state.root_keys.push(super::Key::Inspected);
+            parent.with(state);
+        });
+        entity.unwrap()
+    }
+}
+
+/// Call `visit` with the inspected value of the state, if it has one
+pub(super) fn visit_source(
+    state: &mut super::State,
+    world: &mut World,
+    resources: &mut Resources,
+    visit: &mut dyn FnMut(&mut super::State, &mut dyn Reflect) -> bool,
+) -> bool {
+    // Take the source out of the state so that both can be borrowed during the visit
+    let mut source = match state.inspected.take() {
+        Some(source) => source,
+        None => return false,
+    };
+    let mutated = match &mut source {
+        InspectorSource::Owned(value) => visit(state, &mut **value),
+        InspectorSource::Closure(closure) => {
+            closure(world, resources, &mut |reflect| visit(state, reflect))
+        }
+    };
+    state.inspected = Some(source);
+    mutated
+}
+
+pub(super) fn visit_inspected(
+    commands: &mut Commands,
+    state: &mut super::State,
+    world: &mut World,
+    resources: &mut Resources,
+    type_registry_arc: &TypeRegistry,
+    container: Entity,
+) {
+    let key = super::Key::Inspected;
+    let persistent_id = format!("inspector/{}", state.inspected_name);
+    let entry = {
+        if let Some(entry) = state.entries.get_mut(&key) {
+            entry
+        } else {
+            let name = state.inspected_name.clone();
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                name,
+                persistent_id.clone(),
+                container,
+            );
+            state.entries.insert(
+                key.clone(),
+                super::Entry {
+                    widget: node.root,
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
+                },
+            );
+            state.entries.get_mut(&key).unwrap()
+        }
+    };
+    state.entries_alive.insert(key.clone(), true);
+
+    if let super::EntryType::Node { label, container } = entry.inner {
+        if let Some(container) = container {
+            let changes_seen = state.external_changes;
+            visit_source(state, world, resources, &mut |state, reflect| {
+                state.path_root = Some(Arc::new(key.clone()));
+                state.path_root_name = state.inspected_name.clone();
+                state.path_root_id = persistent_id.clone();
+                let mutated = super::node::dispatch_reflect(
+                    commands,
+                    state,
+                    type_registry_arc.clone(),
+                    reflect,
+                    container,
+                );
+                state.path_root = None;
+                mutated
+            });
+            state.highlight_if_changed(label, changes_seen);
+        }
+    } else {
+        unreachable!();
+    }
+}
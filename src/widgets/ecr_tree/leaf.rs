@@ -9,7 +9,12 @@ use bevy::{
 use serde::de::DeserializeSeed;
 
 use crate::{
-    widgets::{check_box::BuildCheckBox, tree_node::BuildTreeNode},
+    widgets::{
+        check_box::BuildCheckBox,
+        overhead::{Overhead, OverheadPart},
+        tree_node::BuildTreeNode,
+        TextRole,
+    },
     DebugIgnore,
 };
 
@@ -28,9 +33,26 @@ pub fn visit_reflect_leaf(
     variant_index: Option<usize>,
     container: Entity,
 ) -> bool {
+    let path = match state.current_path() {
+        Some(path) => path,
+        None => {
+            warn!("Leaf {} visited outside of a reflected value", name);
+            return false;
+        }
+    };
+    // Compare with the other values edited together
+    let merged = !state.merged_with.is_empty();
+    let mixed = merged && {
+        let current: &dyn Reflect = reflect;
+        state.merged_with.iter_mut().any(|other| {
+            path.resolve(&mut **other).map_or(true, |other| {
+                !other.reflect_partial_eq(current).unwrap_or(false)
+            })
+        })
+    };
     // Insert
     let key = Key::ReflectLeaf {
-        address: reflect as *mut dyn Reflect as *mut () as usize,
+        path,
         type_id: reflect.type_id(),
         variant_index,
     };
@@ -39,7 +61,7 @@ pub fn visit_reflect_leaf(
             entry
         } else {
             let widget = spawner(
-                key,
+                key.clone(),
                 commands,
                 state,
                 _type_registry_arc,
@@ -48,20 +70,22 @@ pub fn visit_reflect_leaf(
                 container,
             );
             state.entries.insert(
-                key,
+                key.clone(),
                 Entry {
                     widget,
                     inner: EntryType::Leaf {
                         value: reflect.clone_value(),
                         field_mutated: true,
                         widget_mutated: None,
+                        from_history: false,
+                        mixed,
                     },
                 },
             );
             state.entries.get_mut(&key).unwrap()
         }
     };
-    state.entries_alive.insert(key, true);
+    state.entries_alive.insert(key.clone(), true);
 
     // Update
     let mut mutated = false;
@@ -69,17 +93,68 @@ pub fn visit_reflect_leaf(
         value,
         field_mutated,
         widget_mutated,
+        from_history,
+        mixed: entry_mixed,
     } = &mut entry.inner
     {
+        if *entry_mixed != mixed {
+            *entry_mixed = mixed;
+            *field_mutated = true;
+        }
         if let Some(widget_mutated) = widget_mutated.take() {
+            if state.attribute.read_only {
+                trace!("Ignoring edit of a read-only field");
+                *from_history = false;
+                // Restore the widget to the current value
+                *field_mutated = true;
+                return false;
+            }
             trace!("Setting value from widget");
+            let old_value = reflect.clone_value();
             reflect.set(widget_mutated).unwrap();
+            if state.attribute.constrain(reflect) {
+                // The widget shows the value before it was constrained
+                *field_mutated = true;
+            }
             value.set(reflect.clone_value()).unwrap();
             mutated = true;
+            if let (true, Key::ReflectLeaf { path, .. }) = (merged, &key) {
+                // The same value is written to the other entities after the visit
+                state
+                    .merged_edits
+                    .push((path.clone(), reflect.clone_value()));
+            }
+            if std::mem::take(from_history) {
+                // The widget did not initiate this change, it needs to be refreshed
+                *field_mutated = true;
+            } else if let Key::ReflectLeaf { path, .. } = &key {
+                let label = format!("{}{}", state.path_root_name, path);
+                let old_values = if merged {
+                    merged_old_values(
+                        &mut state.merged_with,
+                        path,
+                        &*old_value,
+                        reflect.type_id(),
+                        variant_index,
+                    )
+                } else {
+                    Vec::new()
+                };
+                state.changes.push(Change {
+                    key,
+                    label,
+                    type_name: reflect.type_name().to_string(),
+                    old_value,
+                    new_value: reflect.clone_value(),
+                    old_values,
+                });
+            }
         } else if !reflect.reflect_partial_eq(value.as_ref()).unwrap_or(false) {
             trace!("Field has changed");
             value.set(reflect.clone_value()).unwrap();
             *field_mutated = true;
+            state.external_changes += 1;
+            state.highlighted.push(entry.widget);
         }
     } else {
         unreachable!();
@@ -87,6 +162,44 @@ pub fn visit_reflect_leaf(
     mutated
 }
 
+/// Value of a leaf in each of the entities edited together, before they are all overwritten with the edited value
+fn merged_old_values(
+    merged_with: &mut [Box<dyn Reflect>],
+    path: &ReflectPath,
+    old_value: &dyn Reflect,
+    type_id: std::any::TypeId,
+    variant_index: Option<usize>,
+) -> Vec<(Key, Box<dyn Reflect>)> {
+    let (entities, component_type_id) = match &*path.root {
+        Key::SharedComponent { entities, type_id } => (entities, *type_id),
+        _ => return Vec::new(),
+    };
+    // The first entity is the one visited, the other ones were copied to merged_with before the edit
+    let old_values = std::iter::once(Some(old_value.clone_value())).chain(
+        merged_with
+            .iter_mut()
+            .map(|other| path.resolve(&mut **other).map(|value| value.clone_value())),
+    );
+    entities
+        .iter()
+        .zip(old_values)
+        .filter_map(|(entity, value)| {
+            let key = Key::ReflectLeaf {
+                path: ReflectPath {
+                    root: Arc::new(Key::Component {
+                        entity: *entity,
+                        type_id: component_type_id,
+                    }),
+                    segments: path.segments.clone(),
+                },
+                type_id,
+                variant_index,
+            };
+            Some((key, value?))
+        })
+        .collect()
+}
+
 pub fn spawn_widget_default(
     key: super::Key,
     commands: &mut Commands,
@@ -108,9 +221,22 @@ pub fn spawn_widget_default(
             key,
         },
     );
+    if state.attribute.read_only {
+        commands.insert_one(inputbox.widget, crate::widgets::Disabled);
+    }
     inputbox.widget
 }
 
+/// Margin of the labels of the leaves, aligned with the labels of the nodes after their expand icon
+fn label_margin(state: &super::State) -> Rect<Val> {
+    Rect {
+        left: Val::Px(16.0 + 2. * state.style.spacing),
+        right: Val::Px(state.style.spacing),
+        top: Val::Px(state.style.spacing),
+        bottom: Val::Px(state.style.spacing),
+    }
+}
+
 pub fn spawn_widget_unit(
     _key: super::Key,
     commands: &mut Commands,
@@ -138,29 +264,25 @@ pub fn spawn_widget_unit(
                     align_self: AlignSelf::Center,
                     size: Size {
                         width: Val::Undefined,
-                        height: Val::Px(20.),
+                        height: Val::Px(state.style.font_size),
                     },
                     flex_shrink: 1.,
                     flex_grow: 1.,
-                    margin: Rect {
-                        left: Val::Px(26.0),
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        bottom: Val::Px(5.0),
-                    },
+                    margin: label_margin(state),
                     ..Default::default()
                 },
                 text: Text::with_section(
                     format!("{}()", name),
                     TextStyle {
                         font: state.style.font.clone(),
-                        font_size: 20.0,
+                        font_size: state.style.font_size,
                         color: state.style.color_node_text,
                     },
                     Default::default(),
                 ),
                 ..Default::default()
             })
+            .with(TextRole::Text)
             .with(DebugIgnore)
             .current_entity();
     });
@@ -198,29 +320,25 @@ pub fn spawn_widget_unit_active(
                     align_self: AlignSelf::Center,
                     size: Size {
                         width: Val::Undefined,
-                        height: Val::Px(20.),
+                        height: Val::Px(state.style.font_size),
                     },
                     flex_shrink: 1.,
                     flex_grow: 1.,
-                    margin: Rect {
-                        left: Val::Px(26.0),
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        bottom: Val::Px(5.0),
-                    },
+                    margin: label_margin(state),
                     ..Default::default()
                 },
                 text: Text::with_section(
                     name.to_string(),
                     TextStyle {
                         font: state.style.font.clone(),
-                        font_size: 20.0,
+                        font_size: state.style.font_size,
                         color: state.style.color_node_text,
                     },
                     Default::default(),
                 ),
                 ..Default::default()
             })
+            .with(TextRole::Text)
             .with(DebugIgnore)
             .current_entity();
     });
@@ -254,29 +372,25 @@ pub fn spawn_widget_wrong_variant(
                     align_self: AlignSelf::Center,
                     size: Size {
                         width: Val::Undefined,
-                        height: Val::Px(20.),
+                        height: Val::Px(state.style.font_size),
                     },
                     flex_shrink: 1.,
                     flex_grow: 1.,
-                    margin: Rect {
-                        left: Val::Px(26.0),
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        bottom: Val::Px(5.0),
-                    },
+                    margin: label_margin(state),
                     ..Default::default()
                 },
                 text: Text::with_section(
                     name,
                     TextStyle {
                         font: state.style.font.clone(),
-                        font_size: 20.0,
+                        font_size: state.style.font_size,
                         color: state.style.color_node_text,
                     },
                     Default::default(),
                 ),
                 ..Default::default()
             })
+            .with(TextRole::Text)
             .with(DebugIgnore)
             .current_entity();
     });
@@ -303,39 +417,37 @@ pub fn spawn_widget_bool(
     commands.remove_one::<tree_node::Button>(tree_node.button);
 
     commands.set_current_entity(tree_node.button);
+    let mut label = None;
     commands.with_children(|parent| {
-        parent
+        label = parent
             .spawn(TextBundle {
                 style: ui::Style {
                     align_self: AlignSelf::Center,
                     size: Size {
                         width: Val::Undefined,
-                        height: Val::Px(20.),
+                        height: Val::Px(state.style.font_size),
                     },
                     flex_shrink: 1.,
                     flex_grow: 1.,
-                    margin: Rect {
-                        left: Val::Px(26.0),
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        bottom: Val::Px(5.0),
-                    },
+                    margin: label_margin(state),
                     ..Default::default()
                 },
-                text: Text::with_section(
-                    format!("{}bool", name),
-                    TextStyle {
-                        font: state.style.font.clone(),
-                        font_size: 20.0,
-                        color: state.style.color_node_text,
-                    },
-                    Default::default(),
-                ),
+                text: super::node::label_text(state, format!("{}bool", name)),
                 ..Default::default()
             })
+            .with(TextRole::Text)
             .with(DebugIgnore)
             .current_entity();
     });
+    if let Some(text) = state.attribute.tooltip.clone() {
+        commands.insert_one(
+            tree_node.button,
+            super::node::Tooltip {
+                label: label.unwrap(),
+                text,
+            },
+        );
+    }
     let checked = *reflect.downcast_ref().unwrap();
     let checkbox = commands.spawn_check_box(
         checked,
@@ -349,6 +461,9 @@ pub fn spawn_widget_bool(
             key,
         },
     );
+    if state.attribute.read_only {
+        commands.insert_one(checkbox.widget, crate::widgets::Disabled);
+    }
     tree_node.widget
 }
 
@@ -356,7 +471,9 @@ pub fn update_checkbox_system(
     mut query_checkbox: Query<(&mut check_box::Widget, &EntryAccess)>,
     mut query_state: Query<&mut State>,
     mut checkbox_event: EventReader<check_box::ToggledEvent>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     // Propagate widget event to state
     for event in checkbox_event.iter() {
         if let Ok((_checkbox, access)) = query_checkbox.get_mut(event.entity) {
@@ -385,12 +502,14 @@ pub fn update_checkbox_system(
                 if let EntryType::Leaf {
                     value,
                     field_mutated,
+                    mixed,
                     ..
                 } = &mut entry.inner
                 {
                     if *field_mutated {
                         trace!("Propagating field mutation");
                         checkbox.set(*value.downcast_ref().unwrap());
+                        checkbox.set_mixed(*mixed);
                         *field_mutated = false;
                     }
                 } else {
@@ -411,7 +530,9 @@ pub fn update_inputbox_system(
     mut query_state: Query<&mut State>,
     mut inputbox_event: EventReader<input_box::UnfocusedEvent>,
     type_registry_arc: Res<TypeRegistry>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     // Propagate widget event to state
     for event in inputbox_event.iter() {
         if let Ok((_inputbox, access)) = query_inputbox.get_mut(event.entity) {
@@ -421,6 +542,7 @@ pub fn update_inputbox_system(
                         widget_mutated,
                         value,
                         field_mutated,
+                        ..
                     } = &mut entry.inner
                     {
                         trace!("Propagating widget event");
@@ -467,12 +589,17 @@ pub fn update_inputbox_system(
                 if let EntryType::Leaf {
                     value,
                     field_mutated,
+                    mixed,
                     ..
                 } = &mut entry.inner
                 {
                     if *field_mutated {
                         let (cursor, mut text) = query_cursor.get_mut(inputbox.text).unwrap();
-                        if !cursor.is_focused() {
+                        if !cursor.is_focused() && *mixed {
+                            // The entities edited together have different values
+                            text.sections[0].value = "\u{2014}".to_string();
+                            *field_mutated = false;
+                        } else if !cursor.is_focused() {
                             trace!("Propagating field mutation");
                             // Serialize value
                             let type_registry = type_registry_arc.read();
@@ -0,0 +1,626 @@
+use std::hash::Hash;
+
+use bevy::{
+    math::{Rect, Size},
+    prelude::{
+        trace, warn, BuildChildren, ButtonBundle, Commands, DespawnRecursiveExt, Entity,
+        EventReader, FlexDirection, Interaction, Mutated, NodeBundle, Query, Reflect, Res,
+        TextBundle,
+    },
+    reflect::{ReflectMut, ReflectRef, TypeRegistry},
+    text::{Text, TextStyle},
+    ui::{self, AlignItems, AlignSelf, Val},
+    utils::HashMap,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    widgets::{
+        input_box::{self, BuildInputBox},
+        overhead::{Overhead, OverheadPart},
+        TextRole,
+    },
+    DebugIgnore,
+};
+
+use super::{
+    attributes::FieldAttribute, Change, Entry, EntryType, Key, PathSegment, ReflectPath, State,
+};
+
+/// Pending modification of a map, requested through its control widgets
+#[derive(Debug, Clone)]
+pub enum MapEdit {
+    Insert { key: String, value: String },
+    Remove,
+    Rename { new_key: String },
+}
+
+/// Type-erased operations used to add, remove and re-key the entries of a map.
+/// The reflection API does not support inserting into or removing from a concrete map,
+/// so an editor must be registered for each map type with [`State::get_map_editors_mut`].
+#[derive(Clone, Copy)]
+pub struct MapEditor {
+    insert: fn(&mut dyn Reflect, &str, &str) -> Result<(), String>,
+    remove: fn(&mut dyn Reflect, &dyn Reflect) -> Result<(), String>,
+    rename: fn(&mut dyn Reflect, &dyn Reflect, &str) -> Result<(), String>,
+    /// Copy of the concrete map recorded in the history, a `DynamicMap` from `clone_value` cannot be set back
+    clone: fn(&dyn Reflect) -> Option<Box<dyn Reflect>>,
+}
+
+impl MapEditor {
+    /// Editor for a `HashMap<K, V>`, keys and values are parsed from RON
+    pub fn new<K, V>() -> Self
+    where
+        K: Reflect + Eq + Hash + DeserializeOwned,
+        V: DeserializeOwned,
+        HashMap<K, V>: Reflect + Clone,
+    {
+        Self {
+            insert: insert::<K, V>,
+            remove: remove::<K, V>,
+            rename: rename::<K, V>,
+            clone: clone::<K, V>,
+        }
+    }
+}
+
+fn insert<K, V>(map: &mut dyn Reflect, key: &str, value: &str) -> Result<(), String>
+where
+    K: Reflect + Eq + Hash + DeserializeOwned,
+    V: DeserializeOwned,
+    HashMap<K, V>: Reflect,
+{
+    let map = map
+        .downcast_mut::<HashMap<K, V>>()
+        .ok_or_else(|| "wrong map type".to_string())?;
+    let key = ron::de::from_str::<K>(key).map_err(|e| format!("invalid key: {}", e))?;
+    let value = ron::de::from_str::<V>(value).map_err(|e| format!("invalid value: {}", e))?;
+    if map.contains_key(&key) {
+        return Err("key already exists".to_string());
+    }
+    map.insert(key, value);
+    Ok(())
+}
+
+fn remove<K, V>(map: &mut dyn Reflect, key: &dyn Reflect) -> Result<(), String>
+where
+    K: Reflect + Eq + Hash + DeserializeOwned,
+    V: DeserializeOwned,
+    HashMap<K, V>: Reflect,
+{
+    let map = map
+        .downcast_mut::<HashMap<K, V>>()
+        .ok_or_else(|| "wrong map type".to_string())?;
+    let key = key
+        .downcast_ref::<K>()
+        .ok_or_else(|| "wrong key type".to_string())?;
+    map.remove(key)
+        .map(|_| ())
+        .ok_or_else(|| "key not found".to_string())
+}
+
+fn rename<K, V>(map: &mut dyn Reflect, key: &dyn Reflect, new_key: &str) -> Result<(), String>
+where
+    K: Reflect + Eq + Hash + DeserializeOwned,
+    V: DeserializeOwned,
+    HashMap<K, V>: Reflect,
+{
+    let map = map
+        .downcast_mut::<HashMap<K, V>>()
+        .ok_or_else(|| "wrong map type".to_string())?;
+    let key = key
+        .downcast_ref::<K>()
+        .ok_or_else(|| "wrong key type".to_string())?;
+    let new_key = ron::de::from_str::<K>(new_key).map_err(|e| format!("invalid key: {}", e))?;
+    if map.contains_key(&new_key) {
+        return Err("key already exists".to_string());
+    }
+    let value = map.remove(key).ok_or_else(|| "key not found".to_string())?;
+    map.insert(new_key, value);
+    Ok(())
+}
+
+fn clone<K, V>(map: &dyn Reflect) -> Option<Box<dyn Reflect>>
+where
+    K: Reflect + Eq + Hash + DeserializeOwned,
+    V: DeserializeOwned,
+    HashMap<K, V>: Reflect + Clone,
+{
+    map.downcast_ref::<HashMap<K, V>>()
+        .map(|map| Box::new(map.clone()) as Box<dyn Reflect>)
+}
+
+/// Register editors for the most common map types
+pub(super) fn default_editors() -> HashMap<std::any::TypeId, MapEditor> {
+    use std::any::TypeId;
+    let mut editors = HashMap::default();
+    editors.insert(
+        TypeId::of::<HashMap<String, String>>(),
+        MapEditor::new::<String, String>(),
+    );
+    editors.insert(
+        TypeId::of::<HashMap<String, bool>>(),
+        MapEditor::new::<String, bool>(),
+    );
+    editors.insert(
+        TypeId::of::<HashMap<String, i32>>(),
+        MapEditor::new::<String, i32>(),
+    );
+    editors.insert(
+        TypeId::of::<HashMap<String, usize>>(),
+        MapEditor::new::<String, usize>(),
+    );
+    editors.insert(
+        TypeId::of::<HashMap<String, f32>>(),
+        MapEditor::new::<String, f32>(),
+    );
+    editors
+}
+
+pub fn visit_map(
+    commands: &mut Commands,
+    state: &mut State,
+    type_registry_arc: TypeRegistry,
+    reflect: &mut dyn Reflect,
+    container: Entity,
+) -> bool {
+    let map_type_id = reflect.type_id();
+    let editable = state.map_editors.contains_key(&map_type_id) && !state.attribute.read_only;
+    let map_path = match state.current_path() {
+        Some(path) => path,
+        None => {
+            warn!("Map visited outside of a reflected value");
+            return false;
+        }
+    };
+    let mut mutated = false;
+    if editable {
+        mutated |= apply_edits(commands, state, reflect, &map_path, map_type_id);
+    }
+
+    if let ReflectMut::Map(m) = reflect.reflect_mut() {
+        let element_attribute = FieldAttribute::of_element(&state.attribute);
+        let parent_attribute = std::mem::replace(&mut state.attribute, element_attribute);
+        for i in 0..m.len() {
+            let key = m.get_at(i).unwrap().0.clone_value();
+            let key_text = super::serialize_reflect(&*key).unwrap_or_else(|| i.to_string());
+            let value = m.get_mut(&*key).unwrap();
+            state.path.push(PathSegment::MapKey(key_text.clone()));
+            if editable {
+                let control_key = Key::MapEntry {
+                    path: map_path.child(PathSegment::MapKey(key_text.clone())),
+                    type_id: map_type_id,
+                };
+                visit_controls(
+                    commands,
+                    state,
+                    control_key,
+                    spawn_entry_controls,
+                    key_text.clone(),
+                    container,
+                );
+            }
+            mutated |= super::node::visit_reflect_node(
+                commands,
+                state,
+                type_registry_arc.clone(),
+                value,
+                format!("{}: ", key_text),
+                None,
+                container,
+            );
+            state.path.pop();
+        }
+        state.attribute = parent_attribute;
+    }
+
+    if editable {
+        let insert_key = Key::MapInsert {
+            path: map_path,
+            type_id: map_type_id,
+        };
+        visit_controls(
+            commands,
+            state,
+            insert_key,
+            spawn_insert_controls,
+            String::new(),
+            container,
+        );
+    }
+    mutated
+}
+
+fn apply_edits(
+    commands: &mut Commands,
+    state: &mut State,
+    reflect: &mut dyn Reflect,
+    map_path: &ReflectPath,
+    map_type_id: std::any::TypeId,
+) -> bool {
+    let editor = *state.map_editors.get(&map_type_id).unwrap();
+
+    // Collect the pending edits first, the map cannot be modified while iterating it
+    let mut edits = Vec::new();
+    if let ReflectRef::Map(m) = reflect.reflect_ref() {
+        for i in 0..m.len() {
+            let key = m.get_at(i).unwrap().0;
+            let key_text = super::serialize_reflect(key).unwrap_or_else(|| i.to_string());
+            let control_key = Key::MapEntry {
+                path: map_path.child(PathSegment::MapKey(key_text)),
+                type_id: map_type_id,
+            };
+            if let Some(edit) = take_edit(state, &control_key) {
+                edits.push((Some(key.clone_value()), edit));
+            }
+        }
+    }
+    let insert_key = Key::MapInsert {
+        path: map_path.clone(),
+        type_id: map_type_id,
+    };
+    if let Some(edit) = take_edit(state, &insert_key) {
+        edits.push((None, edit));
+    }
+
+    let mut mutated = false;
+    for (key, edit) in edits {
+        trace!("Applying map edit: {:?}", edit);
+        let inserting = matches!(edit, MapEdit::Insert { .. });
+        let old_value = (editor.clone)(reflect);
+        let result = match (key, edit) {
+            (_, MapEdit::Insert { key, value }) => (editor.insert)(reflect, &key, &value),
+            (Some(key), MapEdit::Remove) => (editor.remove)(reflect, &*key),
+            (Some(key), MapEdit::Rename { new_key }) => (editor.rename)(reflect, &*key, &new_key),
+            (None, _) => Err("no entry selected".to_string()),
+        };
+        match result {
+            Ok(()) => {
+                mutated = true;
+                if let (Some(old_value), Some(new_value)) = (old_value, (editor.clone)(reflect)) {
+                    state.changes.push(Change {
+                        key: Key::ReflectNode {
+                            path: map_path.clone(),
+                            type_id: map_type_id,
+                            variant_index: None,
+                        },
+                        label: format!("{}{}", state.path_root_name, map_path),
+                        type_name: reflect.type_name().to_string(),
+                        old_value,
+                        new_value,
+                        old_values: Vec::new(),
+                    });
+                }
+                // Respawn the insert controls to empty their input boxes
+                if inserting {
+                    if let Some(entry) = state.entries.remove(&insert_key) {
+                        commands.despawn_recursive(entry.widget);
+                    }
+                }
+            }
+            Err(e) => warn!("Could not edit map: {}", e),
+        }
+    }
+    mutated
+}
+
+fn take_edit(state: &mut State, key: &Key) -> Option<MapEdit> {
+    if let Some(Entry {
+        inner: EntryType::MapControl { edit, .. },
+        ..
+    }) = state.entries.get_mut(key)
+    {
+        edit.take()
+    } else {
+        None
+    }
+}
+
+type FnSpawnControls = fn(Key, &mut Commands, &mut State, String, Entity) -> Entity;
+
+fn visit_controls(
+    commands: &mut Commands,
+    state: &mut State,
+    key: Key,
+    spawner: FnSpawnControls,
+    key_text: String,
+    container: Entity,
+) {
+    if !state.entries.contains_key(&key) {
+        let widget = spawner(key.clone(), commands, state, key_text, container);
+        state.entries.insert(
+            key.clone(),
+            Entry {
+                widget,
+                inner: EntryType::MapControl {
+                    new_key: String::new(),
+                    new_value: String::new(),
+                    edit: None,
+                },
+            },
+        );
+    }
+    state.entries_alive.insert(key, true);
+}
+
+/// Component of the widgets that edit a map
+#[derive(Debug, Clone)]
+pub struct MapControl {
+    state_entity: Entity,
+    key: Key,
+    action: MapAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapAction {
+    Rename,
+    Remove,
+    NewKey,
+    NewValue,
+    Insert,
+}
+
+fn spawn_row(commands: &mut Commands, state: &State, container: Entity) -> Entity {
+    commands.set_current_entity(container);
+    let mut row = None;
+    commands.with_children(|parent| {
+        row = parent
+            .spawn(NodeBundle {
+                style: ui::Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    flex_shrink: 0.,
+                    margin: Rect {
+                        left: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        top: Val::Px(state.style.spacing),
+                        bottom: Val::Px(0.0),
+                    },
+                    ..Default::default()
+                },
+                material: state.style.style_node.color_button.clone(),
+                ..Default::default()
+            })
+            .with(DebugIgnore)
+            .current_entity();
+    });
+    row.unwrap()
+}
+
+fn label_bundle(label: &str, state: &State) -> TextBundle {
+    TextBundle {
+        style: ui::Style {
+            align_self: AlignSelf::Center,
+            size: Size {
+                width: Val::Undefined,
+                height: Val::Px(state.style.font_size_small()),
+            },
+            flex_shrink: 0.,
+            margin: Rect::all(Val::Px(state.style.spacing)),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            label.to_string(),
+            TextStyle {
+                font: state.style.font.clone(),
+                font_size: state.style.font_size_small(),
+                color: state.style.color_node_text,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+fn spawn_input(
+    commands: &mut Commands,
+    state: &State,
+    key: Key,
+    action: MapAction,
+    text: String,
+) -> Entity {
+    let inputbox = commands.spawn_input_box(
+        state.style.style_input_box.clone(),
+        Some(super::with_debug_ignore),
+    );
+    commands.insert_one(
+        inputbox.widget,
+        MapControl {
+            state_entity: state.state_entity.unwrap(),
+            key,
+            action,
+        },
+    );
+    if !text.is_empty() {
+        commands.insert_one(
+            inputbox.text,
+            Text::with_section(
+                text,
+                TextStyle {
+                    font: state.style.style_input_box.font.clone(),
+                    font_size: state.style.style_input_box.font_size,
+                    color: state.style.style_input_box.color_text,
+                },
+                Default::default(),
+            ),
+        );
+    }
+    inputbox.widget
+}
+
+fn spawn_entry_controls(
+    key: Key,
+    commands: &mut Commands,
+    state: &mut State,
+    key_text: String,
+    container: Entity,
+) -> Entity {
+    let row = spawn_row(commands, state, container);
+    let control = MapControl {
+        state_entity: state.state_entity.unwrap(),
+        key: key.clone(),
+        action: MapAction::Remove,
+    };
+    commands.set_current_entity(row);
+    commands.with_children(|parent| {
+        parent
+            .spawn(ButtonBundle {
+                style: ui::Style {
+                    flex_shrink: 0.,
+                    size: Size {
+                        width: Val::Px(16.0),
+                        height: Val::Px(16.0),
+                    },
+                    margin: Rect::all(Val::Px(state.style.spacing)),
+                    ..Default::default()
+                },
+                material: state.style.icon_remove.clone(),
+                ..Default::default()
+            })
+            .with(control)
+            .with(DebugIgnore)
+            .spawn(label_bundle("key:", state))
+            .with(TextRole::Text)
+            .with(DebugIgnore);
+    });
+    spawn_input(commands, state, key, MapAction::Rename, key_text);
+    row
+}
+
+fn spawn_insert_controls(
+    key: Key,
+    commands: &mut Commands,
+    state: &mut State,
+    _key_text: String,
+    container: Entity,
+) -> Entity {
+    let row = spawn_row(commands, state, container);
+    let control = MapControl {
+        state_entity: state.state_entity.unwrap(),
+        key: key.clone(),
+        action: MapAction::Insert,
+    };
+    commands.set_current_entity(row);
+    commands.with_children(|parent| {
+        parent
+            .spawn(label_bundle("new key:", state))
+            .with(TextRole::Text)
+            .with(DebugIgnore);
+    });
+    spawn_input(
+        commands,
+        state,
+        key.clone(),
+        MapAction::NewKey,
+        String::new(),
+    );
+    commands.with_children(|parent| {
+        parent
+            .spawn(label_bundle("value:", state))
+            .with(TextRole::Text)
+            .with(DebugIgnore);
+    });
+    spawn_input(commands, state, key, MapAction::NewValue, String::new());
+    commands.with_children(|parent| {
+        parent
+            .spawn(ButtonBundle {
+                style: ui::Style {
+                    flex_shrink: 0.,
+                    margin: Rect::all(Val::Px(state.style.spacing)),
+                    ..Default::default()
+                },
+                material: state.style.style_input_box.color_background.clone(),
+                ..Default::default()
+            })
+            .with(control)
+            .with(DebugIgnore)
+            .with_children(|parent| {
+                let mut label = label_bundle("insert", state);
+                label.text.sections[0].style.color = state.style.style_input_box.color_text;
+                label.focus_policy = ui::FocusPolicy::Pass;
+                parent.spawn(label).with(TextRole::Input).with(DebugIgnore);
+            });
+    });
+    row
+}
+
+pub fn update_controls_system(
+    mut inputbox_events: EventReader<input_box::UnfocusedEvent>,
+    query_inputbox: Query<&MapControl>,
+    query_button: Query<(&MapControl, &Interaction), Mutated<Interaction>>,
+    mut query_state: Query<&mut State>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    // Propagate validated text to state
+    for event in inputbox_events.iter() {
+        if event.canceled {
+            continue;
+        }
+        if let Ok(control) = query_inputbox.get(event.entity) {
+            if let Ok(mut state) = query_state.get_mut(control.state_entity) {
+                if let Some(entry) = state.entries.get_mut(&control.key) {
+                    if let EntryType::MapControl {
+                        new_key,
+                        new_value,
+                        edit,
+                    } = &mut entry.inner
+                    {
+                        trace!("Propagating map control text");
+                        match control.action {
+                            MapAction::Rename => {
+                                *edit = Some(MapEdit::Rename {
+                                    new_key: event.text.clone(),
+                                });
+                            }
+                            MapAction::NewKey => *new_key = event.text.clone(),
+                            // The entry is only inserted by the insert button
+                            MapAction::NewValue => *new_value = event.text.clone(),
+                            MapAction::Remove | MapAction::Insert => {}
+                        }
+                    } else {
+                        warn!("Invalid acces");
+                    }
+                } else {
+                    warn!("Invalid acces");
+                }
+            } else {
+                warn!("Invalid acces");
+            }
+        }
+    }
+    // Propagate button clicks to state
+    for (control, interaction) in query_button.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if let Ok(mut state) = query_state.get_mut(control.state_entity) {
+            if let Some(entry) = state.entries.get_mut(&control.key) {
+                if let EntryType::MapControl {
+                    new_key,
+                    new_value,
+                    edit,
+                } = &mut entry.inner
+                {
+                    trace!("Propagating map control click");
+                    match control.action {
+                        MapAction::Remove => *edit = Some(MapEdit::Remove),
+                        MapAction::Insert => {
+                            *edit = Some(MapEdit::Insert {
+                                key: new_key.clone(),
+                                value: new_value.clone(),
+                            });
+                        }
+                        MapAction::Rename | MapAction::NewKey | MapAction::NewValue => {}
+                    }
+                } else {
+                    warn!("Invalid acces");
+                }
+            } else {
+                warn!("Invalid acces");
+            }
+        } else {
+            warn!("Invalid acces");
+        }
+    }
+}
@@ -1,19 +1,31 @@
+pub mod attributes;
 pub mod ecr;
+pub mod inspector;
 pub mod leaf;
+pub mod map;
 pub mod node;
+pub mod viewport;
 
-use std::any::TypeId;
+use std::{any::TypeId, fmt, path::PathBuf, sync::Arc};
 
 use bevy::{
-    prelude::{ChildBuilder, Color, ColorMaterial, Commands, Entity, Handle, Reflect},
-    reflect::TypeRegistry,
+    app::AppExit,
+    prelude::{
+        warn, ChildBuilder, Color, ColorMaterial, Commands, Entity, EventReader, Handle, Reflect,
+        Res, ResMut, Time,
+    },
+    reflect::{ReflectMut, TypeRegistry},
     text::Font,
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
 
-use super::{check_box, input_box, tree_node};
+use super::{
+    check_box, input_box,
+    overhead::{Overhead, OverheadPart},
+    tree_node,
+};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Key {
     #[cfg(feature = "extra")]
     Resource {
@@ -26,18 +38,180 @@ pub enum Key {
         entity: Entity,
         type_id: TypeId,
     },
+    ComponentType {
+        type_id: TypeId,
+    },
+    Archetype {
+        index: u32,
+    },
+    // Component shared by several entities, edited together
+    SharedComponent {
+        entities: Vec<Entity>,
+        type_id: TypeId,
+    },
     ReflectNode {
-        address: usize,
+        path: ReflectPath,
         type_id: TypeId,
         // Used to differenciate enum variants
         variant_index: Option<usize>,
     },
     ReflectLeaf {
-        address: usize,
+        path: ReflectPath,
         type_id: TypeId,
         // Used to differenciate enum variants
         variant_index: Option<usize>,
     },
+    MapEntry {
+        // Path of the entry's value
+        path: ReflectPath,
+        // Type of the map
+        type_id: TypeId,
+    },
+    MapInsert {
+        // Path of the map
+        path: ReflectPath,
+        type_id: TypeId,
+    },
+    // Value of an inspector spawned with [`inspector::BuildInspector`]
+    Inspected,
+    // Explanation shown for components that cannot be reflected
+    ComponentInfo {
+        entity: Entity,
+        type_id: TypeId,
+    },
+}
+
+impl Key {
+    /// Component, resource or inspected value that contains this reflected value
+    pub fn path_root(&self) -> Option<&Key> {
+        match self {
+            Key::ReflectNode { path, .. }
+            | Key::ReflectLeaf { path, .. }
+            | Key::MapEntry { path, .. }
+            | Key::MapInsert { path, .. } => Some(&path.root),
+            _ => None,
+        }
+    }
+    /// Whether the node of this key is the node of `root` or is displayed inside it
+    fn is_within(&self, root: &Key) -> bool {
+        if self == root {
+            return true;
+        }
+        let path = match self {
+            Key::ReflectNode { path, .. }
+            | Key::ReflectLeaf { path, .. }
+            | Key::MapEntry { path, .. }
+            | Key::MapInsert { path, .. } => path,
+            Key::ComponentInfo { entity, type_id } => {
+                return *root
+                    == Key::Component {
+                        entity: *entity,
+                        type_id: *type_id,
+                    }
+            }
+            _ => return false,
+        };
+        match root {
+            Key::ReflectNode {
+                path: root_path, ..
+            } => path.root == root_path.root && path.segments.starts_with(&root_path.segments),
+            _ => *path.root == *root,
+        }
+    }
+}
+
+/// Location of a reflected value inside the component or resource that owns it.
+/// Unlike memory addresses, paths are not invalidated when the value is moved.
+/// The root is shared by all the paths of a visit, so that building a path does not clone it.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ReflectPath {
+    pub root: Arc<Key>,
+    pub segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PathSegment {
+    /// Named field of a struct
+    Field(String),
+    /// Field of a tuple or tuple struct, or element of a list
+    Index(usize),
+    /// Entry of a map, identified by its serialized key
+    MapKey(String),
+    /// Content of the active variant of an enum
+    Variant(usize),
+}
+
+impl ReflectPath {
+    /// Path of a value contained in the value of this path
+    pub fn child(&self, segment: PathSegment) -> ReflectPath {
+        let mut segments = Vec::with_capacity(self.segments.len() + 1);
+        segments.extend_from_slice(&self.segments);
+        segments.push(segment);
+        ReflectPath {
+            root: self.root.clone(),
+            segments,
+        }
+    }
+
+    /// Follow the segments of this path from the value of its root.
+    /// Returns None if the value does not exist anymore.
+    pub fn resolve<'a>(&self, reflect: &'a mut dyn Reflect) -> Option<&'a mut dyn Reflect> {
+        let mut current = reflect;
+        for segment in self.segments.iter() {
+            current = segment.resolve(current)?;
+        }
+        Some(current)
+    }
+}
+
+impl PathSegment {
+    /// Value designated by this segment inside a value, if it exists
+    pub fn resolve<'a>(&self, reflect: &'a mut dyn Reflect) -> Option<&'a mut dyn Reflect> {
+        Some(match (self, reflect.reflect_mut()) {
+            (PathSegment::Field(name), ReflectMut::Struct(s)) => s.field_mut(name)?,
+            (PathSegment::Index(index), ReflectMut::Tuple(t)) => t.field_mut(*index)?,
+            (PathSegment::Index(index), ReflectMut::TupleStruct(ts)) => ts.field_mut(*index)?,
+            (PathSegment::Index(index), ReflectMut::List(l)) => l.get_mut(*index)?,
+            (PathSegment::MapKey(key_text), ReflectMut::Map(m)) => {
+                let key = (0..m.len()).find_map(|i| {
+                    let key = m.get_at(i).unwrap().0;
+                    if serialize_reflect(key).unwrap_or_else(|| i.to_string()) == *key_text {
+                        Some(key.clone_value())
+                    } else {
+                        None
+                    }
+                })?;
+                m.get_mut(&*key)?
+            }
+            #[cfg(feature = "enum")]
+            (PathSegment::Variant(index), ReflectMut::Enum(e)) => {
+                if e.variant_info().index != *index {
+                    return None;
+                }
+                match e.variant_mut() {
+                    bevy::reflect::EnumVariantMut::Unit => return None,
+                    bevy::reflect::EnumVariantMut::NewType(t) => t,
+                    bevy::reflect::EnumVariantMut::Tuple(t) => t.as_reflect_mut(),
+                    bevy::reflect::EnumVariantMut::Struct(s) => s.as_reflect_mut(),
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ReflectPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments.iter() {
+            match segment {
+                PathSegment::Field(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(index) => write!(f, ".{}", index)?,
+                PathSegment::MapKey(key) => write!(f, "[{}]", key)?,
+                PathSegment::Variant(index) => write!(f, "#{}", index)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -46,10 +220,21 @@ struct Entry {
     inner: EntryType,
 }
 
+impl Entry {
+    /// Whether the widget of this entry requested a modification that is not applied yet
+    fn has_pending_edit(&self) -> bool {
+        match &self.inner {
+            EntryType::Leaf { widget_mutated, .. } => widget_mutated.is_some(),
+            EntryType::MapControl { edit, .. } => edit.is_some(),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum EntryType {
     Node {
-        // label: Entity,
+        label: Entity,
         // expand_icon: Entity,
         container: Option<Entity>,
     },
@@ -60,9 +245,37 @@ enum EntryType {
         field_mutated: bool,
         // New value from the widget that needs to be applied
         widget_mutated: Option<Box<dyn Reflect>>,
+        // Whether [widget_mutated] is reverting a previous change and must not be recorded
+        from_history: bool,
+        // Whether the entities edited together have different values
+        mixed: bool,
+    },
+    // Static text
+    Info,
+    MapControl {
+        // Text entered for a new entry
+        new_key: String,
+        new_value: String,
+        // Edit requested by the widget that needs to be applied
+        edit: Option<map::MapEdit>,
     },
 }
 
+/// Value that was modified through a leaf widget
+#[derive(Debug)]
+pub struct Change {
+    /// Key of the modified leaf
+    pub key: Key,
+    /// Human readable location of the modified leaf
+    pub label: String,
+    pub type_name: String,
+    pub old_value: Box<dyn Reflect>,
+    pub new_value: Box<dyn Reflect>,
+    /// Value of the leaf in each entity before the change, when several entities were edited together.
+    /// The keys designate the leaf in the component of each entity.
+    pub old_values: Vec<(Key, Box<dyn Reflect>)>,
+}
+
 pub type FnSpawnWidget =
     fn(Key, &mut Commands, &mut State, TypeRegistry, &mut dyn Reflect, String, Entity) -> Entity;
 
@@ -71,9 +284,47 @@ pub struct State {
     /// Entity that has this component, should always be Some() but State needs to impl Default to be taken out of World
     state_entity: Option<Entity>,
     root_keys: Vec<Key>,
+    /// Owner and path of the reflected value currently being visited
+    path_root: Option<Arc<Key>>,
+    path_root_name: String,
+    path_root_id: String,
+    path: Vec<PathSegment>,
+    /// Attribute of the field currently being visited
+    attribute: attributes::FieldAttribute,
     entries: HashMap<Key, Entry>,
     entries_alive: HashMap<Key, bool>,
     specialized_widgets: HashMap<TypeId, FnSpawnWidget>,
+    map_editors: HashMap<TypeId, map::MapEditor>,
+    changes: Vec<Change>,
+    /// Values of the other entities while visiting a [`Key::SharedComponent`]
+    merged_with: Vec<Box<dyn Reflect>>,
+    /// Values that were edited and must be written to the other entities
+    merged_edits: Vec<(ReflectPath, Box<dyn Reflect>)>,
+    /// Value that is not part of the world, used with [`Key::Inspected`]
+    inspected: Option<inspector::InspectorSource>,
+    inspected_name: String,
+    /// Number of values that were modified outside of the tree, used to highlight the nodes containing them
+    external_changes: usize,
+    /// Labels and widgets to highlight after the update
+    highlighted: Vec<Entity>,
+    /// Root keys that are displayed when the tree is scrolled
+    window: viewport::Window,
+    /// Whether the values are read during this update, see [`LiveUpdates`]
+    refresh: bool,
+    /// Number of root keys during the last update
+    visited_root_keys: usize,
+    /// Whether the expanded components that were not modified keep their content without being visited
+    skip_unchanged: bool,
+    /// Components whose nodes were expanded, collapsed or edited since the last update
+    touched_roots: HashSet<Key>,
+    /// Children container of each expanded component when its content was last visited
+    filled_containers: HashMap<Key, Entity>,
+    /// Components whose content did not change and was not visited during this update
+    skipped_roots: HashSet<Key>,
+    /// Components that were already modified when they were visited, or that the tree modified
+    changed_when_visited: HashSet<Key>,
+    /// Components modified by the systems running after the tree update, see [`ecr::late_changes_system`]
+    late_changes: HashSet<Key>,
     style: Style,
 }
 
@@ -89,28 +340,344 @@ impl State {
             entries: Default::default(),
             entries_alive: Default::default(),
             specialized_widgets,
+            map_editors: map::default_editors(),
+            changes: Default::default(),
+            merged_with: Default::default(),
+            merged_edits: Default::default(),
+            inspected: None,
+            inspected_name: String::new(),
+            external_changes: 0,
+            highlighted: Default::default(),
+            window: Default::default(),
+            refresh: true,
+            visited_root_keys: 0,
+            skip_unchanged: true,
+            touched_roots: Default::default(),
+            filled_containers: Default::default(),
+            skipped_roots: Default::default(),
+            changed_when_visited: Default::default(),
+            late_changes: Default::default(),
             style,
             root_keys: Default::default(),
+            path_root: None,
+            path_root_name: String::new(),
+            path_root_id: String::new(),
+            path: Default::default(),
+            attribute: Default::default(),
         }
     }
+    /// Keep the content of the expanded components that were not modified without visiting them, true by default
+    pub fn set_skip_unchanged(&mut self, skip_unchanged: bool) {
+        self.skip_unchanged = skip_unchanged;
+    }
+    /// Style of the widgets spawned from now on
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
     pub fn get_root_keys_mut(&mut self) -> &mut Vec<Key> {
+        self.window.keys_changed = true;
         &mut self.root_keys
     }
     pub fn get_widgets_mut(&mut self) -> &mut HashMap<TypeId, FnSpawnWidget> {
         &mut self.specialized_widgets
     }
+    pub fn get_map_editors_mut(&mut self) -> &mut HashMap<TypeId, map::MapEditor> {
+        &mut self.map_editors
+    }
+    /// Prevent all the values of this tree from being modified through its widgets
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.attribute.read_only = read_only;
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.attribute.read_only
+    }
+    /// Only spawn the widgets of the root keys that are visible in the viewport, or all of them with None
+    pub fn set_viewport(&mut self, viewport: Option<viewport::Viewport>) {
+        if self.window.viewport != viewport {
+            self.window.viewport = viewport;
+            self.window.moved = true;
+        }
+    }
+    /// Vertical offset of a root key in the tree, including the root keys that have no widget
+    pub fn root_key_offset(&self, key: &Key) -> Option<f32> {
+        self.window.offset_of(key)
+    }
+    /// Highlight the label of a node if one of its values was modified outside of the tree since `changes_seen`
+    fn highlight_if_changed(&mut self, label: Entity, changes_seen: usize) {
+        if self.external_changes != changes_seen {
+            self.highlighted.push(label);
+        }
+    }
+    /// Highlight the label of a collapsed node if its content was modified, and the labels of its ancestors
+    fn highlight_collapsed(&mut self, label: Entity, changed: bool) {
+        if changed {
+            self.external_changes += 1;
+            self.highlighted.push(label);
+        }
+    }
+    /// Whether a value of this root was written through [`State::refresh_leaf`] since the last update,
+    /// its change flags do not come from a modification made outside of the menu
+    fn is_touched(&self, root: &Key) -> bool {
+        self.touched_roots.contains(root)
+    }
+    /// Whether the tree must be updated to react to its widgets, even if the values are not refreshed
+    fn needs_update(&self) -> bool {
+        self.entries.is_empty()
+            || self.root_keys.len() != self.visited_root_keys
+            || !self.touched_roots.is_empty()
+            || self.window.moved
+            || self.entries.values().any(Entry::has_pending_edit)
+    }
+    /// Prepare the next update, the components with pending edits must be visited
+    fn begin_update(&mut self, refresh: bool) {
+        self.skipped_roots.clear();
+        for (key, entry) in self.entries.iter() {
+            if let (true, Some(root)) = (entry.has_pending_edit(), key.path_root()) {
+                self.touched_roots.insert(root.clone());
+            }
+        }
+        self.refresh = refresh;
+    }
+    /// Keep the widgets of a root key without reading its value, when the values are not refreshed and its widgets were not used.
+    /// The components are skipped by [`State::can_skip`] instead, inside the entities, archetypes and component types that contain them.
+    fn keep_without_refresh(&mut self, key: &Key) -> bool {
+        if self.refresh
+            || matches!(
+                key,
+                Key::Entity { .. } | Key::ComponentType { .. } | Key::Archetype { .. }
+            )
+            || !self.entries.contains_key(key)
+            || self.touched_roots.contains(key.path_root().unwrap_or(key))
+        {
+            return false;
+        }
+        for (entry_key, is_alive) in self.entries_alive.iter_mut() {
+            if entry_key.is_within(key) {
+                *is_alive = true;
+            }
+        }
+        true
+    }
+    /// Whether the content of an expanded component can be kept as it is
+    fn can_skip(&self, key: &Key, container: Entity, changed: bool) -> bool {
+        let changed = changed || self.late_changes.contains(key);
+        self.skip_unchanged
+            && !(changed && self.refresh)
+            && !self.touched_roots.contains(key)
+            && self.filled_containers.get(key) == Some(&container)
+    }
+    /// Value inspected with [`inspector::InspectorSource::Owned`]
+    pub fn inspected_value(&self) -> Option<&dyn Reflect> {
+        match &self.inspected {
+            Some(inspector::InspectorSource::Owned(value)) => Some(&**value),
+            _ => None,
+        }
+    }
+    pub fn inspected_value_mut(&mut self) -> Option<&mut dyn Reflect> {
+        match &mut self.inspected {
+            Some(inspector::InspectorSource::Owned(value)) => Some(&mut **value),
+            _ => None,
+        }
+    }
+    /// Returns None outside of the visit of a component, resource or inspected value
+    fn current_path(&self) -> Option<ReflectPath> {
+        let root = self.path_root.as_ref()?;
+        Some(ReflectPath {
+            root: root.clone(),
+            segments: self.path.clone(),
+        })
+    }
+    /// Changes applied through the widgets since the last call
+    pub fn take_changes(&mut self) -> Vec<Change> {
+        std::mem::take(&mut self.changes)
+    }
+    /// Show a value that was written directly to the world, without highlighting it as an external change
+    pub fn refresh_leaf(&mut self, key: &Key, new_value: &dyn Reflect) {
+        if let Some(Entry {
+            inner:
+                EntryType::Leaf {
+                    value,
+                    field_mutated,
+                    ..
+                },
+            ..
+        }) = self.entries.get_mut(key)
+        {
+            if value.set(new_value.clone_value()).is_ok() {
+                *field_mutated = true;
+            }
+        }
+        if let Some(root) = key.path_root() {
+            self.touched_roots.insert(root.clone());
+        }
+    }
+    /// Set the value of a leaf without recording a new change.
+    /// Returns false if the leaf is not currently displayed.
+    pub fn revert_leaf(&mut self, key: &Key, value: Box<dyn Reflect>) -> bool {
+        if let Some(Entry {
+            inner:
+                EntryType::Leaf {
+                    widget_mutated,
+                    from_history,
+                    ..
+                },
+            ..
+        }) = self.entries.get_mut(key)
+        {
+            *widget_mutated = Some(value);
+            *from_history = true;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Style {
     pub font: Handle<Font>,
+    /// Size of the labels and values, the buttons and hints use [`Style::font_size_small`]
+    pub font_size: f32,
+    /// Margin around the labels and the controls, in pixels
+    pub spacing: f32,
     pub style_node: tree_node::Style,
     pub color_node_text: Color,
+    /// Color of the texts drawn directly on the background
+    pub color_text: Color,
     pub color_background: Handle<ColorMaterial>,
     pub style_input_box: input_box::Style,
     pub icon_chevron_down: Handle<ColorMaterial>,
     pub icon_chevron_up: Handle<ColorMaterial>,
     pub style_check_box: check_box::Style,
+    pub icon_remove: Handle<ColorMaterial>,
+    /// Color of the values that were just modified outside of the tree, fading out after [`node::HIGHLIGHT_DURATION`]
+    pub color_highlight: Color,
+}
+
+impl Style {
+    /// Size of the texts of the buttons, of the tooltips and of the hints
+    pub fn font_size_small(&self) -> f32 {
+        self.font_size * 0.8
+    }
+}
+
+/// Throttles the refresh of the values displayed by the trees and by the diagnostics.
+/// The trees still react to their widgets when the values are not refreshed.
+#[derive(Debug, Default)]
+pub struct LiveUpdates {
+    /// Number of refreshes per second, every frame with None
+    pub refresh_rate: Option<f32>,
+    pub paused: bool,
+    elapsed: f32,
+    refresh: bool,
+}
+
+impl LiveUpdates {
+    /// Whether the displayed values are refreshed during this frame
+    pub fn is_refreshing(&self) -> bool {
+        self.refresh
+    }
+}
+
+pub fn live_updates_system(
+    time: Res<Time>,
+    mut live_updates: ResMut<LiveUpdates>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    let live_updates = &mut *live_updates;
+    live_updates.elapsed += time.delta_seconds();
+    live_updates.refresh = match live_updates.refresh_rate {
+        _ if live_updates.paused => false,
+        Some(refresh_rate) if refresh_rate > 0. => {
+            let period = 1. / refresh_rate;
+            if live_updates.elapsed >= period {
+                // Keep the remainder, so that the refreshes do not drift with the frame times
+                live_updates.elapsed %= period;
+                true
+            } else {
+                false
+            }
+        }
+        _ => true,
+    };
+}
+
+/// Identifier of a tree node that stays the same when its panel is respawned or the game restarted
+#[derive(Debug, Clone)]
+pub struct PersistentId(pub String);
+
+/// Tree nodes that are expanded, identified by their [`PersistentId`].
+/// The state can be saved in a file to reopen the same nodes after a restart.
+#[derive(Debug, Default)]
+pub struct ExpansionState {
+    expanded: HashSet<String>,
+    file: Option<PathBuf>,
+    /// Modified since the file was last saved
+    dirty: bool,
+    since_save: f32,
+}
+
+/// Minimum time between two saves of the expansion state, in seconds
+const EXPANSION_SAVE_INTERVAL: f32 = 2.;
+
+impl ExpansionState {
+    /// Load the state from a RON file if it exists, and save it there after changes and on exit
+    pub fn from_file(file: impl Into<PathBuf>) -> Self {
+        let file = file.into();
+        let expanded = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| ron::de::from_str::<Vec<String>>(&content).ok())
+            .unwrap_or_default();
+        Self {
+            expanded: expanded.into_iter().collect(),
+            file: Some(file),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_expanded(&self, id: &str) -> bool {
+        self.expanded.contains(id)
+    }
+
+    pub fn set_expanded(&mut self, id: String, expanded: bool) {
+        let changed = if expanded {
+            self.expanded.insert(id)
+        } else {
+            self.expanded.remove(&id)
+        };
+        self.dirty |= changed;
+    }
+
+    fn save(&mut self) {
+        self.dirty = false;
+        self.since_save = 0.;
+        if let Some(file) = self.file.as_ref() {
+            let mut expanded = self.expanded.iter().collect::<Vec<_>>();
+            expanded.sort();
+            let result = ron::ser::to_string_pretty(&expanded, Default::default())
+                .map_err(|e| e.to_string())
+                .and_then(|content| std::fs::write(file, content).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                warn!("Could not save expansion state to {:?}: {}", file, e);
+            }
+        }
+    }
+}
+
+/// Save the expansion state at most every [`EXPANSION_SAVE_INTERVAL`], and when the app exits
+pub fn save_expansion_system(
+    time: Res<Time>,
+    mut exit_events: EventReader<AppExit>,
+    mut expansion_state: ResMut<ExpansionState>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    expansion_state.since_save += time.delta_seconds();
+    let exiting = exit_events.iter().next().is_some();
+    if expansion_state.dirty && (exiting || expansion_state.since_save >= EXPANSION_SAVE_INTERVAL) {
+        expansion_state.save();
+    }
 }
 
 pub struct EntryAccess {
@@ -118,11 +685,21 @@ pub struct EntryAccess {
     key: Key,
 }
 
+impl EntryAccess {
+    /// Entity that has the [`State`] of the tree
+    pub fn state_entity(&self) -> Entity {
+        self.state_entity
+    }
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+}
+
 pub fn with_debug_ignore(parent: &mut ChildBuilder) {
     parent.with(crate::DebugIgnore);
 }
 
-fn serialize_reflect(reflect: &dyn Reflect) -> Option<String> {
+pub(crate) fn serialize_reflect(reflect: &dyn Reflect) -> Option<String> {
     let serializable = reflect.serializable()?;
     let serialize = serializable.borrow();
     ron::to_string(&serialize).ok()
@@ -1,17 +1,22 @@
 use bevy::{
-    ecs::With,
+    ecs::{Component, With},
     math::{Rect, Size},
     prelude::{
-        trace, warn, BuildChildren, ColorMaterial, Commands, Entity, Handle, ImageBundle, Mutated,
-        Query, Reflect, TextBundle,
+        trace, warn, Added, BuildChildren, ButtonBundle, ChildBuilder, Children, Color,
+        ColorMaterial, Commands, Entity, EventReader, Events, Handle, ImageBundle, Interaction,
+        Mutated, Parent, Query, Reflect, Res, ResMut, TextBundle, Time, World,
     },
     reflect::TypeRegistry,
-    text::{Text, TextStyle},
-    ui::{self, AlignSelf, Val},
+    text::{Text, TextSection, TextStyle},
+    ui::{self, AlignSelf, FocusPolicy, Val},
 };
 
 use crate::{
-    widgets::tree_node::{self, BuildTreeNode},
+    widgets::{
+        overhead::{Overhead, OverheadPart},
+        tree_node::{self, BuildTreeNode},
+        TextRole,
+    },
     DebugIgnore,
 };
 
@@ -28,11 +33,39 @@ pub fn dispatch_reflect(
     reflect: &mut dyn Reflect,
     container: Entity,
 ) -> bool {
+    // Maps are edited as a whole to be able to insert and remove entries
+    if let bevy::reflect::ReflectRef::Map(_) = reflect.reflect_ref() {
+        return super::map::visit_map(commands, state, type_registry_arc, reflect, container);
+    }
+
     let mut mutated = false;
+    let type_id = reflect.type_id();
+    // Elements only inherit the attribute of their container, the other options are specific to the container
+    let element_attribute = super::attributes::FieldAttribute::of_element(&state.attribute);
     match reflect.reflect_mut() {
         bevy::reflect::ReflectMut::Struct(s) => {
+            let field_attributes = type_registry_arc
+                .read()
+                .get(type_id)
+                .and_then(|registration| registration.data::<super::attributes::FieldAttributes>())
+                .cloned();
             for i in 0..s.field_len() {
-                let name = format!("{}: ", s.name_at(i).unwrap());
+                let field_name = s.name_at(i).unwrap().to_string();
+                let attribute = field_attributes
+                    .as_ref()
+                    .and_then(|field_attributes| field_attributes.get(&field_name))
+                    .cloned()
+                    .unwrap_or_default();
+                if attribute.hidden {
+                    continue;
+                }
+                let name = format!(
+                    "{}: ",
+                    attribute.display_name.as_deref().unwrap_or(&field_name)
+                );
+                let attribute = attribute.inherit(&state.attribute);
+                let parent_attribute = std::mem::replace(&mut state.attribute, attribute);
+                state.path.push(super::PathSegment::Field(field_name));
                 mutated |= super::node::visit_reflect_node(
                     commands,
                     state,
@@ -42,11 +75,15 @@ pub fn dispatch_reflect(
                     None,
                     container,
                 );
+                state.path.pop();
+                state.attribute = parent_attribute;
             }
         }
         bevy::reflect::ReflectMut::Tuple(t) => {
+            let parent_attribute = std::mem::replace(&mut state.attribute, element_attribute);
             for i in 0..t.field_len() {
                 let name = format!("{}: ", i);
+                state.path.push(super::PathSegment::Index(i));
                 mutated |= super::node::visit_reflect_node(
                     commands,
                     state,
@@ -56,11 +93,15 @@ pub fn dispatch_reflect(
                     None,
                     container,
                 );
+                state.path.pop();
             }
+            state.attribute = parent_attribute;
         }
         bevy::reflect::ReflectMut::TupleStruct(ts) => {
+            let parent_attribute = std::mem::replace(&mut state.attribute, element_attribute);
             for i in 0..ts.field_len() {
                 let name = format!("{}: ", i);
+                state.path.push(super::PathSegment::Index(i));
                 mutated |= super::node::visit_reflect_node(
                     commands,
                     state,
@@ -70,11 +111,15 @@ pub fn dispatch_reflect(
                     None,
                     container,
                 );
+                state.path.pop();
             }
+            state.attribute = parent_attribute;
         }
         bevy::reflect::ReflectMut::List(l) => {
+            let parent_attribute = std::mem::replace(&mut state.attribute, element_attribute);
             for i in 0..l.len() {
                 let name = format!("[{}]:", i);
+                state.path.push(super::PathSegment::Index(i));
                 mutated |= super::node::visit_reflect_node(
                     commands,
                     state,
@@ -84,27 +129,11 @@ pub fn dispatch_reflect(
                     None,
                     container,
                 );
+                state.path.pop();
             }
+            state.attribute = parent_attribute;
         }
-        bevy::reflect::ReflectMut::Map(m) => {
-            for i in 0..m.len() {
-                let key = m.get_at(i).unwrap().0.clone_value();
-                let name = format!(
-                    "{}: ",
-                    super::serialize_reflect(&*key).unwrap_or_else(|| i.to_string())
-                );
-                let value = m.get_mut(&*key).unwrap();
-                mutated |= super::node::visit_reflect_node(
-                    commands,
-                    state,
-                    type_registry_arc.clone(),
-                    value,
-                    name,
-                    None,
-                    container,
-                );
-            }
-        }
+        bevy::reflect::ReflectMut::Map(_) => unreachable!(),
         bevy::reflect::ReflectMut::Value(v) => {
             mutated |= super::leaf::visit_reflect_leaf(
                 &(super::leaf::spawn_widget_default as super::FnSpawnWidget),
@@ -120,6 +149,7 @@ pub fn dispatch_reflect(
         #[cfg(feature = "enum")]
         bevy::reflect::ReflectMut::Enum(e) => {
             let index = e.variant_info().index;
+            let parent_attribute = std::mem::replace(&mut state.attribute, element_attribute);
             for i in 0..e.iter_variants_info().count() {
                 let variant_name = e.get_index_name(i).unwrap().to_string();
                 if i == index {
@@ -150,6 +180,7 @@ pub fn dispatch_reflect(
                             s.as_reflect_mut(),
                         ),
                     };
+                    state.path.push(super::PathSegment::Variant(i));
                     mutated |= super::node::visit_reflect_node(
                         commands,
                         state,
@@ -159,6 +190,7 @@ pub fn dispatch_reflect(
                         Some(i),
                         container,
                     );
+                    state.path.pop();
                 } else {
                     super::leaf::visit_reflect_leaf(
                         &(super::leaf::spawn_widget_wrong_variant as super::FnSpawnWidget),
@@ -172,6 +204,7 @@ pub fn dispatch_reflect(
                     );
                 }
             }
+            state.attribute = parent_attribute;
         }
     }
     mutated
@@ -201,9 +234,16 @@ pub fn visit_reflect_node(
     }
 
     // Generic node widget
+    let path = match state.current_path() {
+        Some(path) => path,
+        None => {
+            warn!("Node {} visited outside of a reflected value", name);
+            return false;
+        }
+    };
     let mut mutated = false;
     let key = super::Key::ReflectNode {
-        address: reflect as *mut dyn Reflect as *mut () as usize,
+        path,
         type_id: reflect.type_id(),
         variant_index,
     };
@@ -217,13 +257,27 @@ pub fn visit_reflect_node(
             } else {
                 format!("{}{} (unregistered)", name, reflect.type_name())
             };
-            let node = super::node::spawn_widget_node(key, commands, state, label, container);
+            let persistent_id = match &key {
+                super::Key::ReflectNode { path, .. } => format!("{}{}", state.path_root_id, path),
+                _ => unreachable!(),
+            };
+            let node = super::node::spawn_widget_node(
+                key.clone(),
+                commands,
+                state,
+                label,
+                persistent_id,
+                container,
+            );
 
             state.entries.insert(
-                key,
+                key.clone(),
                 super::Entry {
                     widget: node.root,
-                    inner: super::EntryType::Node { container: None },
+                    inner: super::EntryType::Node {
+                        label: node.label,
+                        container: None,
+                    },
                 },
             );
             state.entries.get_mut(&key).unwrap()
@@ -231,9 +285,11 @@ pub fn visit_reflect_node(
     };
     state.entries_alive.insert(key, true);
 
-    if let super::EntryType::Node { container, .. } = entry.inner {
+    if let super::EntryType::Node { label, container } = entry.inner {
         if let Some(container) = container {
+            let changes_seen = state.external_changes;
             mutated |= dispatch_reflect(commands, state, type_registry_arc, reflect, container);
+            state.highlight_if_changed(label, changes_seen);
         }
     } else {
         unreachable!();
@@ -251,6 +307,7 @@ pub fn spawn_widget_node(
     commands: &mut Commands,
     state: &mut super::State,
     name: String,
+    persistent_id: String,
     container: Entity,
 ) -> NodeBuilder {
     commands.set_current_entity(container);
@@ -258,12 +315,43 @@ pub fn spawn_widget_node(
         state.style.style_node.clone(),
         Some(super::with_debug_ignore),
     );
-    commands.insert_one(
+    // Only the keys that can be used as roots can be pinned
+    let pin = match key {
+        #[cfg(feature = "extra")]
+        super::Key::Resource { .. } => Some(PinButton { key: key.clone() }),
+        super::Key::Entity { .. } | super::Key::Component { .. } => {
+            Some(PinButton { key: key.clone() })
+        }
+        // Inspected values and selections only exist in their own tree
+        super::Key::ReflectNode { ref path, .. }
+            if !matches!(
+                *path.root,
+                super::Key::Inspected | super::Key::SharedComponent { .. }
+            ) =>
+        {
+            Some(PinButton { key: key.clone() })
+        }
+        _ => None,
+    };
+    let select = match key {
+        super::Key::Entity { entity } => Some(SelectButton { entity }),
+        _ => None,
+    };
+    let snapshot = match key {
+        #[cfg(feature = "extra")]
+        super::Key::Resource { .. } => Some(SnapshotButton { key: key.clone() }),
+        super::Key::Entity { .. } => Some(SnapshotButton { key: key.clone() }),
+        _ => None,
+    };
+    commands.insert(
         tree_node.widget,
-        super::EntryAccess {
-            state_entity: state.state_entity.unwrap(),
-            key,
-        },
+        (
+            super::EntryAccess {
+                state_entity: state.state_entity.unwrap(),
+                key,
+            },
+            super::PersistentId(persistent_id),
+        ),
     );
     let mut label = None;
     let mut expand_icon = None;
@@ -273,7 +361,7 @@ pub fn spawn_widget_node(
             .spawn(ImageBundle {
                 style: ui::Style {
                     align_self: AlignSelf::Center,
-                    margin: Rect::all(Val::Px(5.0)),
+                    margin: Rect::all(Val::Px(state.style.spacing)),
                     flex_shrink: 0.,
                     size: Size {
                         width: Val::Px(16.0),
@@ -297,50 +385,383 @@ pub fn spawn_widget_node(
                     align_self: AlignSelf::Center,
                     size: Size {
                         width: Val::Undefined,
-                        height: Val::Px(20.),
+                        height: Val::Px(state.style.font_size),
                     },
                     flex_shrink: 0.,
                     margin: Rect {
                         left: Val::Px(0.0),
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        bottom: Val::Px(5.0),
+                        right: Val::Px(state.style.spacing),
+                        top: Val::Px(state.style.spacing),
+                        bottom: Val::Px(state.style.spacing),
                     },
                     ..Default::default()
                 },
-                text: Text::with_section(
-                    name,
-                    TextStyle {
-                        font: state.style.font.clone(),
-                        font_size: 20.0,
-                        color: state.style.color_node_text,
-                    },
-                    Default::default(),
-                ),
+                text: label_text(state, name),
                 ..Default::default()
             })
+            .with(TextRole::Text)
             .with(DebugIgnore)
             .current_entity();
+        // The buttons are aligned to the right of the node
+        let mut margin_left = Val::Auto;
+        if let Some(select) = select {
+            spawn_node_button(parent, state, "select", margin_left, select);
+            margin_left = Val::Px(0.0);
+        }
+        if let Some(snapshot) = snapshot {
+            spawn_node_button(parent, state, "snapshot", margin_left, snapshot);
+            margin_left = Val::Px(0.0);
+        }
+        if let Some(pin) = pin {
+            spawn_node_button(parent, state, "pin", margin_left, pin);
+        }
     });
+    if let Some(text) = state.attribute.tooltip.clone() {
+        commands.insert_one(
+            tree_node.button,
+            Tooltip {
+                label: label.unwrap(),
+                text,
+            },
+        );
+    }
     NodeBuilder {
         root: tree_node.widget,
         label: label.unwrap(),
     }
 }
 
+fn spawn_node_button(
+    parent: &mut ChildBuilder,
+    state: &super::State,
+    text: &str,
+    margin_left: Val,
+    marker: impl Component,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: ui::Style {
+                align_self: AlignSelf::Center,
+                flex_shrink: 0.,
+                margin: Rect {
+                    left: margin_left,
+                    right: Val::Px(state.style.spacing),
+                    top: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                },
+                ..Default::default()
+            },
+            material: state.style.style_node.color_button.clone(),
+            ..Default::default()
+        })
+        .with(marker)
+        .with(DebugIgnore)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    style: ui::Style {
+                        size: Size {
+                            width: Val::Undefined,
+                            height: Val::Px(state.style.font_size_small()),
+                        },
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        text,
+                        TextStyle {
+                            font: state.style.font.clone(),
+                            font_size: state.style.font_size_small(),
+                            color: state.style.color_node_text,
+                        },
+                        Default::default(),
+                    ),
+                    focus_policy: FocusPolicy::Pass,
+                    ..Default::default()
+                })
+                .with(TextRole::Text)
+                .with(DebugIgnore);
+        });
+}
+
+/// Text of a field label, with an empty section to show its tooltip
+pub(super) fn label_text(state: &super::State, name: String) -> Text {
+    let mut text = Text::with_section(
+        name,
+        TextStyle {
+            font: state.style.font.clone(),
+            font_size: state.style.font_size,
+            color: state.style.color_node_text,
+        },
+        Default::default(),
+    );
+    if state.attribute.tooltip.is_some() {
+        text.sections.push(TextSection {
+            value: String::new(),
+            style: TextStyle {
+                font: state.style.font.clone(),
+                font_size: state.style.font_size_small(),
+                color: state.style.color_node_text,
+            },
+        });
+    }
+    text
+}
+
+/// Tooltip of a field, shown in its label while the button is hovered
+pub struct Tooltip {
+    pub label: Entity,
+    pub text: String,
+}
+
+pub fn tooltip_system(
+    query_button: Query<(&Tooltip, &Interaction), Mutated<Interaction>>,
+    mut query_text: Query<&mut Text>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (tooltip, interaction) in query_button.iter() {
+        if let Ok(mut text) = query_text.get_mut(tooltip.label) {
+            if let Some(section) = text.sections.get_mut(1) {
+                section.value = match interaction {
+                    Interaction::None => String::new(),
+                    _ => format!(" ({})", tooltip.text),
+                };
+            }
+        }
+    }
+}
+
+/// Duration of the highlight of a value that was modified outside of the tree, in seconds
+pub const HIGHLIGHT_DURATION: f32 = 1.0;
+
+/// Tints the texts of a widget, fading back to their own color
+pub struct Highlight {
+    color: Color,
+    remaining: f32,
+    /// Texts of the widget with their own color and the tinted color last written
+    texts: Vec<HighlightedText>,
+}
+
+struct HighlightedText {
+    entity: Entity,
+    section: usize,
+    color: Color,
+    tinted: Color,
+}
+
+impl Highlight {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            remaining: HIGHLIGHT_DURATION,
+            texts: Vec::new(),
+        }
+    }
+    pub fn restart(&mut self) {
+        self.remaining = HIGHLIGHT_DURATION;
+    }
+}
+
+pub fn highlight_system(
+    commands: &mut Commands,
+    time: Res<Time>,
+    mut query_highlight: Query<(Entity, &mut Highlight)>,
+    query_children: Query<&Children>,
+    mut query_text: Query<&mut Text>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (entity, mut highlight) in query_highlight.iter_mut() {
+        let highlight = &mut *highlight;
+        highlight.remaining -= time.delta_seconds();
+        let ratio = (highlight.remaining / HIGHLIGHT_DURATION).max(0.);
+        // The texts are collected again, they can be respawned or restyled during the highlight
+        let mut entities = Vec::new();
+        collect_descendants(entity, &query_children, &mut entities);
+        let previous_texts = std::mem::take(&mut highlight.texts);
+        for text_entity in entities {
+            if let Ok(mut text) = query_text.get_mut(text_entity) {
+                for (index, section) in text.sections.iter_mut().enumerate() {
+                    // A color that is not the tint written by the highlight is the new color of the text
+                    let color = previous_texts
+                        .iter()
+                        .find(|previous| {
+                            previous.entity == text_entity && previous.section == index
+                        })
+                        .filter(|previous| previous.tinted == section.style.color)
+                        .map_or(section.style.color, |previous| previous.color);
+                    let tinted = mix_colors(color, highlight.color, ratio);
+                    section.style.color = tinted;
+                    highlight.texts.push(HighlightedText {
+                        entity: text_entity,
+                        section: index,
+                        color,
+                        tinted,
+                    });
+                }
+            }
+        }
+        if highlight.remaining <= 0. {
+            commands.remove_one::<Highlight>(entity);
+        }
+    }
+}
+
+/// Entities in the hierarchy of a widget, including the widget
+fn collect_descendants(
+    entity: Entity,
+    query_children: &Query<&Children>,
+    entities: &mut Vec<Entity>,
+) {
+    entities.push(entity);
+    if let Ok(children) = query_children.get(entity) {
+        for child in children.iter() {
+            collect_descendants(*child, query_children, entities);
+        }
+    }
+}
+
+fn mix_colors(from: Color, to: Color, ratio: f32) -> Color {
+    Color::rgba(
+        from.r() + (to.r() - from.r()) * ratio,
+        from.g() + (to.g() - from.g()) * ratio,
+        from.b() + (to.b() - from.b()) * ratio,
+        from.a() + (to.a() - from.a()) * ratio,
+    )
+}
+
+/// Point the buttons of a node to another key, when its widget is recycled.
+/// The buttons are next to the label of the node.
+pub(super) fn rebind_node_buttons(world: &mut World, label: Entity, key: &super::Key) {
+    let buttons = match world
+        .get::<Parent>(label)
+        .ok()
+        .and_then(|parent| world.get::<Children>(parent.0).ok())
+    {
+        Some(children) => children.iter().copied().collect::<Vec<_>>(),
+        None => return,
+    };
+    for button in buttons {
+        if let Ok(mut pin) = world.get_mut::<PinButton>(button) {
+            pin.key = key.clone();
+        }
+        if let Ok(mut snapshot) = world.get_mut::<SnapshotButton>(button) {
+            snapshot.key = key.clone();
+        }
+        if let (Ok(mut select), super::Key::Entity { entity }) =
+            (world.get_mut::<SelectButton>(button), key)
+        {
+            select.entity = *entity;
+        }
+    }
+}
+
+/// Button that adds or removes a root node from the watch list
+pub struct PinButton {
+    key: super::Key,
+}
+
+impl PinButton {
+    pub fn key(&self) -> &super::Key {
+        &self.key
+    }
+}
+
+/// Sent when a pin button is clicked
+#[derive(Debug)]
+pub struct PinEvent {
+    pub key: super::Key,
+}
+
+/// Button that adds or removes an entity from the selection
+pub struct SelectButton {
+    entity: Entity,
+}
+
+/// Sent when a select button is clicked
+#[derive(Debug)]
+pub struct SelectEvent {
+    pub entity: Entity,
+}
+
+/// Button that freezes the values of an entity or a resource in the Snapshots panel
+pub struct SnapshotButton {
+    key: super::Key,
+}
+
+/// Sent when a snapshot button is clicked
+#[derive(Debug)]
+pub struct SnapshotEvent {
+    pub key: super::Key,
+}
+
+pub fn snapshot_button_system(
+    query_button: Query<(&SnapshotButton, &Interaction), Mutated<Interaction>>,
+    mut snapshot_events: ResMut<Events<SnapshotEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (snapshot, interaction) in query_button.iter() {
+        if *interaction == Interaction::Clicked {
+            trace!("Snapshot requested: {:?}", snapshot.key);
+            snapshot_events.send(SnapshotEvent {
+                key: snapshot.key.clone(),
+            });
+        }
+    }
+}
+
+pub fn select_button_system(
+    query_button: Query<(&SelectButton, &Interaction), Mutated<Interaction>>,
+    mut select_events: ResMut<Events<SelectEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (select, interaction) in query_button.iter() {
+        if *interaction == Interaction::Clicked {
+            trace!("Entity selected: {:?}", select.entity);
+            select_events.send(SelectEvent {
+                entity: select.entity,
+            });
+        }
+    }
+}
+
+pub fn pin_button_system(
+    query_button: Query<(&PinButton, &Interaction), Mutated<Interaction>>,
+    mut pin_events: ResMut<Events<PinEvent>>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (pin, interaction) in query_button.iter() {
+        if *interaction == Interaction::Clicked {
+            trace!("Node pinned: {:?}", pin.key);
+            pin_events.send(PinEvent {
+                key: pin.key.clone(),
+            });
+        }
+    }
+}
+
 pub fn update_node_system(
     mut query_icon: Query<(&ExpandIcon, &mut Handle<ColorMaterial>)>,
     query_node: Query<&tree_node::Widget, Mutated<tree_node::Widget>>,
     query_access: Query<&super::EntryAccess, With<tree_node::Widget>>,
     mut query_state: Query<&mut super::State>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for access in query_access.iter() {
         if let Ok(mut state) = query_state.get_mut(access.state_entity) {
+            let state = &mut *state;
             if let Some(entry) = state.entries.get_mut(&access.key) {
                 if let super::EntryType::Node { container, .. } = &mut entry.inner {
                     if let Ok(node) = query_node.get(entry.widget) {
                         trace!("Updating node children container for {:?}", entry.widget);
                         *container = node.get_children_container();
+                        // The component containing the node must be visited to fill or empty it
+                        let root = access.key.path_root().unwrap_or(&access.key);
+                        state.touched_roots.insert(root.clone());
                     }
                 } else {
                     warn!("Invalid acces");
@@ -367,3 +788,37 @@ pub fn update_node_system(
         }
     }
 }
+
+/// Expand the nodes that were expanded the last time they were displayed
+pub fn restore_expansion_system(
+    commands: &mut Commands,
+    mut query_node: Query<
+        (Entity, &mut tree_node::Widget, &super::PersistentId),
+        Added<super::PersistentId>,
+    >,
+    expansion_state: Res<super::ExpansionState>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for (entity, mut node, persistent_id) in query_node.iter_mut() {
+        if !node.is_expanded() && expansion_state.is_expanded(&persistent_id.0) {
+            trace!("Restoring expanded node {}", persistent_id.0);
+            node.toggle_expand(entity, commands);
+        }
+    }
+}
+
+/// Remember which nodes are expanded by the user
+pub fn record_expansion_system(
+    mut expanded_events: EventReader<tree_node::ExpandedEvent>,
+    query_node: Query<&super::PersistentId>,
+    mut expansion_state: ResMut<super::ExpansionState>,
+    overhead: Res<Overhead>,
+) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
+    for event in expanded_events.iter() {
+        if let Ok(persistent_id) = query_node.get(event.widget) {
+            expansion_state.set_expanded(persistent_id.0.clone(), event.expanded);
+        }
+    }
+}
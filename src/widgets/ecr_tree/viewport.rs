@@ -0,0 +1,245 @@
+use std::{cmp::Ordering, ops::Range};
+
+use bevy::{
+    math::Size,
+    prelude::{BuildChildren, Children, Commands, Entity, GlobalTransform, NodeBundle, World},
+    ui::{self, Node, Val},
+    utils::HashMap,
+};
+
+use crate::{widgets::tree_node, DebugIgnore};
+
+/// Height of a collapsed root node, until one is measured
+pub const DEFAULT_ROW_HEIGHT: f32 = 30.0;
+/// Rows spawned above and below the visible ones, so that scrolling does not show empty space before the next update
+const OVERSCAN: usize = 5;
+
+/// Part of a tree that is visible in a scrolled panel, in pixels from the top of its container
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub offset: f32,
+    pub height: f32,
+}
+
+/// Only the root keys inside the viewport get widgets, the others are replaced by two spacers of the same height
+#[derive(Debug, Default)]
+pub(super) struct Window {
+    pub(super) viewport: Option<Viewport>,
+    /// Whether the viewport changed since the last update
+    pub(super) moved: bool,
+    /// Measured height of a collapsed root node
+    row_height: Option<f32>,
+    /// Heights of the expanded root nodes, measured while they were displayed
+    heights: HashMap<super::Key, f32>,
+    /// Whether the root keys changed since the offsets were computed
+    pub(super) keys_changed: bool,
+    /// Whether a measured height changed since the offsets were computed
+    heights_changed: bool,
+    /// Top of each root key followed by the total height, so that scrolling does not go through all the keys
+    offsets: Vec<f32>,
+    indices: HashMap<super::Key, usize>,
+    /// Height of the other children of the container, displayed above the rows
+    header_height: f32,
+    /// Collapsed entity rows that left the viewport, bound to the entities that enter it instead of spawning new rows
+    pub(super) free_rows: Vec<(Entity, Entity)>,
+    /// Root keys that have widgets
+    pub(super) range: Range<usize>,
+    /// Height of the rows before and after the range
+    height_before: f32,
+    height_after: f32,
+    spacers: Option<(Entity, Entity)>,
+}
+
+impl Window {
+    fn height(&self, key: &super::Key) -> f32 {
+        self.heights
+            .get(key)
+            .copied()
+            .unwrap_or_else(|| self.row_height.unwrap_or(DEFAULT_ROW_HEIGHT))
+    }
+
+    /// Measure the root nodes displayed during the last update, the layout has been computed since then
+    pub(super) fn measure(
+        &mut self,
+        world: &World,
+        container: Entity,
+        keys: &[super::Key],
+        entries: &HashMap<super::Key, super::Entry>,
+    ) {
+        if let Some((before, _)) = self.spacers {
+            // Distance between the top of the container and the top of the rows
+            let top = |entity| {
+                let node = world.get::<Node>(entity).ok()?;
+                let transform = world.get::<GlobalTransform>(entity).ok()?;
+                Some(transform.translation.y + node.size.y / 2.)
+            };
+            if let (Some(container_top), Some(rows_top)) = (top(container), top(before)) {
+                self.header_height = (container_top - rows_top).max(0.);
+            }
+        }
+        let range = self.range.start.min(keys.len())..self.range.end.min(keys.len());
+        for key in keys[range].iter() {
+            let widget = match entries.get(key) {
+                Some(entry) => entry.widget,
+                None => continue,
+            };
+            let height = match world.get::<Node>(widget) {
+                Ok(node) if node.size.y > 0. => node.size.y,
+                _ => continue,
+            };
+            let expanded = world
+                .get::<tree_node::Widget>(widget)
+                .map(|widget| widget.is_expanded())
+                .unwrap_or(false);
+            if expanded {
+                if self.heights.insert(key.clone(), height) != Some(height) {
+                    self.heights_changed = true;
+                }
+            } else {
+                if self.heights.remove(key).is_some() {
+                    self.heights_changed = true;
+                }
+                if self.row_height != Some(height) {
+                    self.row_height = Some(height);
+                    self.heights_changed = true;
+                }
+            }
+        }
+    }
+
+    /// Compute the offsets of the root keys again when they or their heights changed
+    fn update_offsets(&mut self, keys: &[super::Key]) {
+        let keys_changed = self.keys_changed || self.offsets.len() != keys.len() + 1;
+        if keys_changed {
+            self.indices = keys
+                .iter()
+                .enumerate()
+                .map(|(index, key)| (key.clone(), index))
+                .collect();
+        }
+        if keys_changed || self.heights_changed {
+            let mut top = 0.;
+            self.offsets.clear();
+            for key in keys.iter() {
+                self.offsets.push(top);
+                top += self.height(key);
+            }
+            self.offsets.push(top);
+        }
+        self.keys_changed = false;
+        self.heights_changed = false;
+    }
+
+    /// Select the root keys that are visible, all of them if there is no viewport
+    pub(super) fn update_range(&mut self, keys: &[super::Key]) -> Range<usize> {
+        let viewport = match self.viewport {
+            Some(viewport) => viewport,
+            None => {
+                self.range = 0..keys.len();
+                return self.range.clone();
+            }
+        };
+        self.update_offsets(keys);
+        let offset = viewport.offset - self.header_height;
+        // Rows that end above the viewport, and rows that start above its bottom
+        let first = count_below(&self.offsets[1..], offset, true);
+        let last = count_below(&self.offsets[..keys.len()], offset + viewport.height, false);
+        let first = first.saturating_sub(OVERSCAN);
+        let last = (last + OVERSCAN).min(keys.len()).max(first);
+        self.height_before = self.offsets[first];
+        self.height_after = self.offsets[keys.len()] - self.offsets[last];
+        self.range = first..last;
+        self.range.clone()
+    }
+
+    /// Vertical offset of a root key, used to scroll to a node that has no widget
+    pub(super) fn offset_of(&self, key: &super::Key) -> Option<f32> {
+        let index = *self.indices.get(key)?;
+        Some(self.header_height + self.offsets.get(index)?)
+    }
+
+    /// Spawn or resize the spacers that take the place of the hidden rows
+    pub(super) fn update_spacers(
+        &mut self,
+        commands: &mut Commands,
+        world: &mut World,
+        container: Entity,
+    ) {
+        match (self.viewport, self.spacers) {
+            (None, Some((before, after))) => {
+                commands.despawn(before);
+                commands.despawn(after);
+                self.spacers = None;
+            }
+            (Some(_), Some((before, after))) => {
+                for (spacer, height) in [(before, self.height_before), (after, self.height_after)]
+                    .iter()
+                    .copied()
+                {
+                    if let Ok(mut style) = world.get_mut::<ui::Style>(spacer) {
+                        if style.size.height != Val::Px(height) {
+                            style.size.height = Val::Px(height);
+                        }
+                    }
+                }
+            }
+            (Some(_), None) => {
+                let mut spacers = Vec::new();
+                commands.set_current_entity(container);
+                commands.with_children(|parent| {
+                    for height in [self.height_before, self.height_after].iter() {
+                        parent
+                            .spawn(NodeBundle {
+                                style: ui::Style {
+                                    size: Size::new(Val::Undefined, Val::Px(*height)),
+                                    flex_shrink: 0.,
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            })
+                            .with(DebugIgnore);
+                        spacers.push(parent.current_entity().unwrap());
+                    }
+                });
+                self.spacers = Some((spacers[0], spacers[1]));
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Place the widgets of the visible root keys between the spacers, in the order of the keys.
+    /// Must be called once the widgets spawned during the update were added to the container.
+    pub(super) fn reorder(&self, world: &mut World, container: Entity, rows: &[Entity]) {
+        let (before, after) = match self.spacers {
+            Some(spacers) => spacers,
+            None => return,
+        };
+        if let Ok(mut children) = world.get_mut::<Children>(container) {
+            // Other children of the container stay above the rows
+            let mut order = children
+                .iter()
+                .copied()
+                .filter(|child| *child != before && *child != after && !rows.contains(child))
+                .collect::<Vec<_>>();
+            order.push(before);
+            order.extend(rows.iter().filter(|row| children.contains(row)));
+            order.push(after);
+            if order[..] != children[..] {
+                *children = Children::with(&order);
+            }
+        }
+    }
+}
+
+/// Number of sorted values that are below `y`, or equal to it if `inclusive`
+fn count_below(values: &[f32], y: f32, inclusive: bool) -> usize {
+    match values.binary_search_by(|value| {
+        if *value < y || (inclusive && *value <= y) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(index) | Err(index) => index,
+    }
+}
@@ -4,6 +4,11 @@ use bevy::{
     ui::{self, FocusPolicy},
 };
 
+use super::{
+    overhead::{Overhead, OverheadPart},
+    TextRole,
+};
+
 // TODO
 // - implement more control characters
 // - test WASM for issues with out-of-order events
@@ -20,6 +25,8 @@ pub struct Widget {
 pub struct Style {
     pub color_background: Handle<ColorMaterial>,
     pub font: Handle<Font>,
+    pub font_size: f32,
+    pub color_text: Color,
 }
 
 pub struct Builder {
@@ -45,6 +52,11 @@ impl BuildInputBox for Commands {
                 .spawn(NodeBundle {
                     style: ui::Style {
                         margin: Rect::all(Val::Px(2.0)),
+                        // Keep empty boxes clickable
+                        min_size: Size {
+                            width: Val::Px(32.0),
+                            height: Val::Undefined,
+                        },
                         ..Default::default()
                     },
                     material: style.color_background.clone(),
@@ -65,7 +77,7 @@ impl BuildInputBox for Commands {
                                 margin: Rect::all(Val::Px(2.0)),
                                 size: Size {
                                     width: Val::Undefined,
-                                    height: Val::Px(16.),
+                                    height: Val::Px(style.font_size),
                                 },
                                 flex_shrink: 0.,
                                 ..Default::default()
@@ -74,13 +86,14 @@ impl BuildInputBox for Commands {
                                 String::new(),
                                 TextStyle {
                                     font: style.font.clone(),
-                                    font_size: 16.0,
-                                    color: Color::BLACK,
+                                    font_size: style.font_size,
+                                    color: style.color_text,
                                 },
                                 Default::default(),
                             ),
                             ..Default::default()
                         })
+                        .with(TextRole::Input)
                         .with(FocusPolicy::Pass)
                         .with(Cursor::default())
                         .current_entity();
@@ -205,6 +218,11 @@ impl Cursor {
     }
 }
 
+/// Whether text is being edited in an input box, keyboard shortcuts must not be stolen from it
+pub fn text_input_focused(query_cursor: &Query<&Cursor>) -> bool {
+    query_cursor.iter().any(|cursor| cursor.is_focused())
+}
+
 impl Default for Cursor {
     fn default() -> Self {
         Self {
@@ -228,12 +246,17 @@ pub struct FocusedEvent {
 }
 
 pub fn interact_mouse_system(
-    query_widget: Query<(Entity, &Widget, &Interaction), Mutated<Interaction>>,
+    query_widget: Query<
+        (Entity, &Widget, &Interaction),
+        (Mutated<Interaction>, Without<super::Disabled>),
+    >,
     mut query_cursor: Query<(&mut Cursor, &mut Text, &GlobalTransform)>,
     mut focused_events: ResMut<Events<FocusedEvent>>,
     text_pipeline: Res<DefaultTextPipeline>,
     windows: Res<Windows>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     if let Some(mouse_pos) = windows
         .get_primary()
         .and_then(|window| window.cursor_position())
@@ -280,7 +303,9 @@ pub fn interact_keyboard_system(
     mut query: Query<(&Parent, &mut Text, &mut Cursor)>,
     windows: Res<Windows>,
     mut unfocused_events: ResMut<Events<UnfocusedEvent>>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (parent, mut text, mut cursor) in query.iter_mut() {
         if cursor.cursor_pos.is_some() {
             let value = &mut text.sections[0].value;
@@ -1,27 +1,51 @@
 pub mod check_box;
 pub mod ecr_tree;
 pub mod input_box;
+pub mod overhead;
 pub mod radio_button;
 pub mod tree_node;
 
 pub use check_box::BuildCheckBox;
+pub use ecr_tree::inspector::BuildInspector;
 pub use input_box::BuildInputBox;
 pub use radio_button::BuildRadioButtons;
 pub use tree_node::BuildTreeNode;
 
 use bevy::prelude::{stage, AppBuilder, IntoSystem, Plugin};
 
+/// Marker component for input boxes and check boxes that do not react to user inputs
+#[derive(Debug, Clone, Copy)]
+pub struct Disabled;
+
+/// Color of the theme used by a text, to restyle it when the theme changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRole {
+    /// Texts drawn directly on the background
+    Title,
+    /// Texts drawn on the buttons and on the boxes
+    Text,
+    /// Undone edits in the History panel
+    Disabled,
+    /// Texts of the input boxes
+    Input,
+}
+
 pub struct WidgetsPlugin;
 
 impl Plugin for WidgetsPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(ecr_tree::ecr::update_system.system())
+        app.init_resource::<ecr_tree::LiveUpdates>()
+            .init_resource::<overhead::Overhead>()
+            .add_system_to_stage(stage::PRE_UPDATE, ecr_tree::live_updates_system.system())
+            .add_system(ecr_tree::ecr::update_system.system())
+            .add_system_to_stage(stage::LAST, ecr_tree::ecr::late_changes_system.system())
             .add_system_to_stage(
                 /*stage::POST_UPDATE*/ stage::UPDATE,
                 ecr_tree::ecr::update_entity_labels.system(),
             ) // listens to Mutated<Name>, Mutated<Label> and Added<EntityLabel>
             .add_system(ecr_tree::leaf::update_checkbox_system.system())
             .add_system(ecr_tree::leaf::update_inputbox_system.system())
+            .add_system(ecr_tree::map::update_controls_system.system())
             .add_event::<input_box::UnfocusedEvent>()
             .add_event::<input_box::FocusedEvent>()
             .add_system(input_box::interact_mouse_system.system())
@@ -29,6 +53,18 @@ impl Plugin for WidgetsPlugin {
             .add_event::<tree_node::ExpandedEvent>()
             .add_system(tree_node::interact_button_system.system())
             .add_system(ecr_tree::node::update_node_system.system()) // after tree_node::interact_button_system
+            .add_system(ecr_tree::node::tooltip_system.system())
+            .add_system(ecr_tree::node::highlight_system.system())
+            .init_resource::<ecr_tree::ExpansionState>()
+            .add_system(ecr_tree::node::restore_expansion_system.system())
+            .add_system(ecr_tree::node::record_expansion_system.system())
+            .add_system(ecr_tree::save_expansion_system.system()) // after ecr_tree::node::record_expansion_system
+            .add_event::<ecr_tree::node::PinEvent>()
+            .add_system(ecr_tree::node::pin_button_system.system())
+            .add_event::<ecr_tree::node::SelectEvent>()
+            .add_system(ecr_tree::node::select_button_system.system())
+            .add_event::<ecr_tree::node::SnapshotEvent>()
+            .add_system(ecr_tree::node::snapshot_button_system.system())
             .add_event::<radio_button::SelectionChangedEvent>()
             .add_system(radio_button::interact_system.system())
             .add_system(check_box::interact_system.system())
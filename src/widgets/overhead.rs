@@ -0,0 +1,72 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Time spent by the debug menu during the current frame.
+/// The durations are atomics so that the measured systems only need a shared access and can still run in parallel.
+/// The diagnostics reporting them are registered by the debug menu.
+#[derive(Debug, Default)]
+pub struct Overhead {
+    tree: Arc<AtomicU64>,
+    diagnostics: Arc<AtomicU64>,
+    widgets: Arc<AtomicU64>,
+    menu: Arc<AtomicU64>,
+}
+
+/// Group of systems whose time is reported by a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverheadPart {
+    Tree,
+    Diagnostics,
+    Widgets,
+    /// Systems of the panels of the debug menu
+    Menu,
+}
+
+impl Overhead {
+    /// Start measuring a system, the time is added when the returned guard is dropped.
+    /// The guard does not borrow the resource, so that exclusive systems can use the resources while they are measured.
+    pub fn measure(&self, part: OverheadPart) -> Measure {
+        Measure {
+            total: self.total(part).clone(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Add the duration of a system that cannot keep a borrow of the resource while running
+    pub fn add(&self, part: OverheadPart, duration: Duration) {
+        self.total(part)
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn total(&self, part: OverheadPart) -> &Arc<AtomicU64> {
+        match part {
+            OverheadPart::Tree => &self.tree,
+            OverheadPart::Diagnostics => &self.diagnostics,
+            OverheadPart::Widgets => &self.widgets,
+            OverheadPart::Menu => &self.menu,
+        }
+    }
+
+    /// Time measured since the last call, in milliseconds
+    pub(crate) fn take_millis(&self, part: OverheadPart) -> f64 {
+        self.total(part).swap(0, Ordering::Relaxed) as f64 / 1_000_000.
+    }
+}
+
+/// Guard returned by [`Overhead::measure`]
+pub struct Measure {
+    total: Arc<AtomicU64>,
+    start: Instant,
+}
+
+impl Drop for Measure {
+    fn drop(&mut self) {
+        let nanos = self.start.elapsed().as_nanos() as u64;
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
@@ -1,11 +1,13 @@
 use bevy::{
     prelude::{
         BuildChildren, ButtonBundle, ChildBuilder, ColorMaterial, Entity, Events, Handle,
-        Interaction, Mutated, NodeBundle, Query, ResMut,
+        Interaction, Mutated, NodeBundle, Query, Res, ResMut,
     },
     ui,
 };
 
+use super::overhead::{Overhead, OverheadPart};
+
 #[derive(Debug)]
 pub struct Widget {
     buttons: Vec<Entity>,
@@ -106,6 +108,37 @@ impl Widget {
         previous.map(|index| (index, self.buttons[index]))
     }
 
+    /// Select the button at this position as if it was clicked, returns the event to send if the selection changed
+    pub fn select_index(
+        &mut self,
+        widget: Entity,
+        index: usize,
+        material_query: &mut Query<&mut Handle<ColorMaterial>>,
+    ) -> Option<SelectionChangedEvent> {
+        if self.selection == Some(index) {
+            return None;
+        }
+        let button = *self.buttons.get(index)?;
+        let previous = self.select(button);
+        if let Some((_previous_index, previous_button)) = previous {
+            if let Ok(mut material) = material_query.get_mut(previous_button) {
+                *material = self.style.color_button.clone();
+            }
+        }
+        if let Ok(mut material) = material_query.get_mut(button) {
+            *material = self
+                .style
+                .color_button_selected
+                .clone()
+                .unwrap_or_else(|| self.style.color_button.clone());
+        }
+        Some(SelectionChangedEvent {
+            widget,
+            new_selection: self.selection,
+            previous_selection: previous.map(|(index, _entity)| index),
+        })
+    }
+
     pub fn is_selected(&self, button: Entity) -> bool {
         let position = self.buttons.iter().position(|&entity| entity == button);
         position == self.selection
@@ -125,7 +158,9 @@ pub fn interact_system(
     mut material_query: Query<&mut Handle<ColorMaterial>>,
     mut container_query: Query<&mut Widget>,
     mut selection_changed_events: ResMut<Events<SelectionChangedEvent>>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (entity, interaction, radio_button) in interaction_query.iter() {
         let mut material = material_query.get_mut(entity).unwrap();
         let mut widget = container_query.get_mut(radio_button.widget).unwrap();
@@ -2,11 +2,13 @@ use bevy::{
     prelude::{
         trace, BuildChildren, ButtonBundle, ChildBuilder, ColorMaterial, Commands,
         DespawnRecursiveExt, Entity, Events, FlexDirection, Handle, Interaction, Mutated,
-        NodeBundle, Parent, Query, ResMut, Visible, With,
+        NodeBundle, Parent, Query, Res, ResMut, Visible, With,
     },
     ui,
 };
 
+use super::overhead::{Overhead, OverheadPart};
+
 pub struct Widget {
     pub style: Style,
     children_container: Option<Entity>,
@@ -154,7 +156,9 @@ pub fn interact_button_system(
     mut tree_node_query: Query<&mut Widget>,
     commands: &mut Commands,
     mut expanded_events: ResMut<Events<ExpandedEvent>>,
+    overhead: Res<Overhead>,
 ) {
+    let _measure = overhead.measure(OverheadPart::Widgets);
     for (interaction, mut material, Parent(parent)) in interaction_query.iter_mut() {
         let mut tree_node = tree_node_query.get_mut(*parent).unwrap();
         match *interaction {